@@ -0,0 +1,5 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS kyubey.alert (
+    key character varying NOT NULL PRIMARY KEY,
+    sent_at timestamp with time zone NOT NULL DEFAULT now()
+);
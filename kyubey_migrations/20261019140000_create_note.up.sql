@@ -0,0 +1,13 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS kyubey.note (
+    id bigserial PRIMARY KEY,
+    system_id character varying NOT NULL,
+    run_id character varying,
+    task_id character varying,
+    attempt integer,
+    author character varying NOT NULL,
+    body text NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS note_target_idx ON kyubey.note (system_id, run_id, task_id, attempt);
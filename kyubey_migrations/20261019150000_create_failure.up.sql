@@ -0,0 +1,9 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS kyubey.failure (
+    run_id character varying NOT NULL PRIMARY KEY,
+    assignee character varying,
+    acknowledged_by character varying,
+    acknowledged_at timestamp with time zone,
+    resolved_by character varying,
+    resolved_at timestamp with time zone
+);
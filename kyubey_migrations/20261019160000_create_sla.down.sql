@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS kyubey.sla_breach;
+DROP TABLE IF EXISTS kyubey.sla;
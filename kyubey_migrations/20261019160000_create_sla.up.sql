@@ -0,0 +1,19 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS kyubey.sla (
+    system_id character varying NOT NULL,
+    dag_id character varying,
+    max_duration_minutes integer,
+    deadline_minutes integer
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS sla_target_idx ON kyubey.sla (system_id, COALESCE(dag_id, ''));
+
+CREATE TABLE IF NOT EXISTS kyubey.sla_breach (
+    run_id character varying NOT NULL PRIMARY KEY,
+    system_id character varying NOT NULL,
+    dag_id character varying NOT NULL,
+    due_at timestamp with time zone NOT NULL,
+    recorded_at timestamp with time zone NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS sla_breach_system_idx ON kyubey.sla_breach (system_id);
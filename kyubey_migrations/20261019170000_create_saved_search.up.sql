@@ -0,0 +1,6 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS kyubey.saved_search (
+    name character varying NOT NULL PRIMARY KEY,
+    search_by character varying NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT now()
+);
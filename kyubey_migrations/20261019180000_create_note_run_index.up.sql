@@ -0,0 +1,2 @@
+-- Add up migration script here
+CREATE INDEX IF NOT EXISTS note_run_idx ON kyubey.note (run_id, task_id, attempt);
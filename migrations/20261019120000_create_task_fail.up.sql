@@ -0,0 +1,11 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS task_fail (
+    id integer NOT NULL,
+    task_id character varying(250) NOT NULL,
+    dag_id character varying(250) NOT NULL,
+    run_id character varying(250) NOT NULL,
+    map_index integer DEFAULT '-1'::integer NOT NULL,
+    start_date timestamp with time zone,
+    end_date timestamp with time zone,
+    duration integer
+);
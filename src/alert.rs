@@ -0,0 +1,156 @@
+use color_eyre::eyre;
+use kyubey::{
+    core::{Anomaly, attention_read},
+    db::{alert_keys_delete_except, alert_keys_insert, alert_keys_select},
+};
+use poem_openapi::{Object, types::ToJSON};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::{collections::HashSet, time::Duration};
+use tokio::{sync::watch, time::interval};
+
+/// How often we look for new anomalies to alert on
+const ALERT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Longest we wait on the alert webhook, so a hung endpoint can't stall alerts or shutdown
+const ALERT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What we post to the alert webhook, `text` keeps it readable in chat tools like Slack
+#[derive(Object)]
+struct AlertPayload {
+    text: String,
+    environment: String,
+    anomalies: Vec<Anomaly>,
+}
+
+/// Watch for stuck and long running work, posting anything new to the alert webhook.
+/// With Kyubey's own schema, what we alerted on survives restarts.
+/// Stops between checks once shutdown is signalled.
+pub async fn alert_loop(
+    pool: PgPool,
+    webhook_url: String,
+    kyubey_schema: bool,
+    environment: String,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let client = match reqwest::Client::builder().timeout(ALERT_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(err) => {
+            tracing::error!("Unable to build the alert client for {environment}: {err}");
+            return;
+        }
+    };
+    let mut alerted: Option<HashSet<String>> = None;
+    let mut ticker = interval(ALERT_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.changed() => return,
+        }
+
+        let result: Result<(), eyre::Error> = alert_once(
+            &pool,
+            &client,
+            &webhook_url,
+            kyubey_schema,
+            &environment,
+            &mut alerted,
+        )
+        .await;
+        if let Err(err) = result {
+            tracing::error!("Unable to send alerts for {environment}: {err}");
+        }
+    }
+}
+
+/// Look for anomalies once, and post the ones we have not alerted on yet
+async fn alert_once(
+    pool: &PgPool,
+    client: &reqwest::Client,
+    webhook_url: &str,
+    kyubey_schema: bool,
+    environment: &str,
+    alerted: &mut Option<HashSet<String>>,
+) -> Result<(), eyre::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await?;
+
+    // Pick up where we left off before a restart
+    let alerted: &mut HashSet<String> = match alerted {
+        Some(alerted) => alerted,
+        None if kyubey_schema => {
+            alerted.insert(alert_keys_select(&mut tx).await?.into_iter().collect())
+        }
+        None => alerted.insert(HashSet::new()),
+    };
+
+    // Everything that needs attention right now
+    let anomalies: Vec<Anomaly> = attention_read(&mut tx).await?;
+
+    // Forget anything that cleared up, so it alerts again if it comes back
+    let current: HashSet<String> = anomalies.iter().map(Anomaly::key).collect();
+    alerted.retain(|key: &String| current.contains(key));
+    if kyubey_schema {
+        let current: Vec<String> = current.into_iter().collect();
+        alert_keys_delete_except(&mut tx, &current).await?;
+    }
+
+    // Done with the database until the webhook answers, so a slow one holds no connection
+    tx.commit().await?;
+
+    // Only tell people about what is new
+    let new: Vec<Anomaly> = anomalies
+        .into_iter()
+        .filter(|anomaly: &Anomaly| !alerted.contains(&anomaly.key()))
+        .collect();
+    if new.is_empty() {
+        return Ok(());
+    }
+
+    let lines: Vec<String> = new
+        .iter()
+        .map(|anomaly: &Anomaly| {
+            format!(
+                "{}: {} / {} {} {}{}",
+                anomaly.kind,
+                anomaly.client_name,
+                anomaly.system_name,
+                anomaly.dag_id,
+                anomaly.run_id,
+                anomaly
+                    .task_id
+                    .as_ref()
+                    .map(|task_id: &String| format!(" {}", task_id))
+                    .unwrap_or_default(),
+            )
+        })
+        .collect();
+    let keys: Vec<String> = new.iter().map(Anomaly::key).collect();
+    let payload = AlertPayload {
+        text: format!(
+            "Kyubey found {} new anomalies in {}\n{}",
+            new.len(),
+            environment,
+            lines.join("\n")
+        ),
+        environment: environment.to_string(),
+        anomalies: new,
+    };
+
+    // Post to the webhook, only remembering what we sent once it went through
+    client
+        .post(webhook_url)
+        .header("Content-Type", "application/json")
+        .body(payload.to_json_string())
+        .send()
+        .await?
+        .error_for_status()?;
+    if kyubey_schema {
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await?;
+        alert_keys_insert(&mut tx, &keys).await?;
+        tx.commit().await?;
+    }
+    alerted.extend(keys);
+
+    Ok(())
+}
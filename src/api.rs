@@ -1,23 +1,39 @@
-use crate::{
-    Config,
+use kyubey::{
+    Config, Environment,
     core::{
-        DagRun, DagRunTasks, System, SystemDagRuns, Task, dag_run_read, dag_runs_for_system_read,
-        log_read, search_systems_read, system_read, task_read, tasks_for_dag_run_read,
+        Anomaly, CalendarDay, DagRun, DagRunTasks, EnvironmentSearch, Failure, FailureAction,
+        FailureStatus, FailureUpdate, LogBundleFormat, LogDiff, LogLevel, LogLine, NewNote,
+        NewSavedSearch, Note, NoteTarget, Readiness, SavedSearch, ScorecardWindow, System,
+        SystemDagRuns, SystemDurations, SystemFlakyTasks, SystemScorecard, SystemSlas, Task,
+        attention_read, dag_run_read, dag_runs_for_system_read, failure_update, failures_read,
+        log_bundle_read, log_diff_read, log_lines_read, log_read, note_create, notes_read,
+        readiness_read, saved_search_remove, saved_search_save, saved_searches_read,
+        scorecard_read, search_environments_read, search_systems_read, strip_ansi,
+        system_calendar_read, system_durations_read, system_flaky_tasks_read, system_read,
+        system_slas_read, task_read, tasks_for_dag_run_read,
     },
+    error::{Entity, KyubeyError},
 };
-use poem::{error::InternalServerError, web::Data};
+use poem::{Body, Response, handler, http::StatusCode, web::Data};
 use poem_openapi::{
-    OpenApi, Tags,
+    Enum, Object, OpenApi, Tags,
     param::{Path, Query},
-    payload::{Json, PlainText},
+    payload::{Attachment, AttachmentType, Json, PlainText},
+    types::ToJSON,
 };
 use sqlx::{PgPool, Postgres, Transaction};
+use std::io::ErrorKind;
 
 #[derive(Tags)]
 enum Tag {
+    Analytics,
+    Attention,
     #[oai(rename = "Dag Run")]
     DagRun,
+    Failure,
     Log,
+    Note,
+    Reliability,
     System,
     Task,
 }
@@ -35,10 +51,10 @@ impl Api {
         Query(system_id): Query<String>,
     ) -> Result<Json<System>, poem::Error> {
         // Start Transaction
-        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(InternalServerError)?;
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
 
         // Search for anything that meets our criteria
-        let system: System = system_read(&mut tx, &system_id).await?;
+        let system: System = system_read(&mut tx, &system_id).await.map_err(http_error)?;
 
         Ok(Json(system))
     }
@@ -48,50 +64,376 @@ impl Api {
     async fn search_systems_get(
         &self,
         Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
         Query(search_by): Query<String>,
         Query(page): Query<u32>,
     ) -> Result<Json<Vec<System>>, poem::Error> {
         // Start Transaction
-        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(InternalServerError)?;
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
 
         // Search for anything that meets our criteria
-        let systems: Vec<System> = search_systems_read(&mut tx, &search_by, &page).await?;
+        let systems: Vec<System> = search_systems_read(&mut tx, config, &search_by, &page)
+            .await
+            .map_err(http_error)?;
 
         Ok(Json(systems))
     }
 
+    /// Search for your system in every environment at once, tagged with where each lives
+    #[oai(path = "/search_environments", method = "get", tag = Tag::System)]
+    async fn search_environments_get(
+        &self,
+        Data(environments): Data<&Vec<Environment>>,
+        Query(search_by): Query<String>,
+        Query(page): Query<u32>,
+    ) -> Json<EnvironmentSearch> {
+        // Search every environment for anything that meets our criteria
+        Json(search_environments_read(environments, &search_by, &page).await)
+    }
+
+    /// Searches saved to run again, by name
+    #[oai(path = "/saved_searches", method = "get", tag = Tag::System)]
+    async fn saved_searches_get(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
+    ) -> Result<Json<Vec<SavedSearch>>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Every saved search
+        let saved_searches: Vec<SavedSearch> = saved_searches_read(&mut tx, config)
+            .await
+            .map_err(http_error)?;
+
+        Ok(Json(saved_searches))
+    }
+
+    /// Save a search under a name, replacing any search already saved under it
+    #[oai(path = "/saved_searches", method = "post", tag = Tag::System)]
+    async fn saved_search_post(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
+        Json(new_saved_search): Json<NewSavedSearch>,
+    ) -> Result<Json<SavedSearch>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Save the search
+        let saved_search: SavedSearch = saved_search_save(&mut tx, config, &new_saved_search)
+            .await
+            .map_err(http_error)?;
+
+        // Commit Transaction
+        tx.commit().await.map_err(database_error)?;
+
+        Ok(Json(saved_search))
+    }
+
+    /// Forget a saved search
+    #[oai(path = "/saved_searches/:name", method = "delete", tag = Tag::System)]
+    async fn saved_search_delete(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
+        Path(name): Path<String>,
+    ) -> Result<(), poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Forget the search
+        saved_search_remove(&mut tx, config, &name)
+            .await
+            .map_err(http_error)?;
+
+        // Commit Transaction
+        tx.commit().await.map_err(database_error)?;
+
+        Ok(())
+    }
+
+    /// How long a System's Dags and Tasks take, with outlier runs
+    #[oai(path = "/durations/:system_id", method = "get", tag = Tag::Analytics)]
+    async fn durations_get(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Path(system_id): Path<String>,
+    ) -> Result<Json<SystemDurations>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Duration statistics for a System
+        let durations: SystemDurations = system_durations_read(&mut tx, &system_id)
+            .await
+            .map_err(http_error)?;
+
+        Ok(Json(durations))
+    }
+
+    /// A System's Tasks that needed retries recently, flakiest first
+    #[oai(path = "/flaky_tasks/:system_id", method = "get", tag = Tag::Analytics)]
+    async fn flaky_tasks_get(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
+        Path(system_id): Path<String>,
+    ) -> Result<Json<SystemFlakyTasks>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Flakiest Tasks for a System
+        let flaky: SystemFlakyTasks = system_flaky_tasks_read(&mut tx, config, &system_id)
+            .await
+            .map_err(http_error)?;
+
+        Ok(Json(flaky))
+    }
+
+    /// Stuck Dag Runs and long running Dag Runs and Tasks, longest first
+    #[oai(path = "/attention", method = "get", tag = Tag::Attention)]
+    async fn attention_get(
+        &self,
+        Data(pool): Data<&PgPool>,
+    ) -> Result<Json<Vec<Anomaly>>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Everything that needs attention right now
+        let anomalies: Vec<Anomaly> = attention_read(&mut tx).await.map_err(http_error)?;
+
+        Ok(Json(anomalies))
+    }
+
+    /// A System's SLAs and every Dag Run that breached them, latest first
+    #[oai(path = "/sla/:system_id", method = "get", tag = Tag::Reliability)]
+    async fn slas_get(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
+        Path(system_id): Path<String>,
+    ) -> Result<Json<SystemSlas>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // SLA breaches for a System
+        let slas: SystemSlas = system_slas_read(&mut tx, config, &system_id)
+            .await
+            .map_err(http_error)?;
+
+        Ok(Json(slas))
+    }
+
+    /// Success rates, recovery times and retry rates for every System and Dag
+    #[oai(path = "/scorecard", method = "get", tag = Tag::Reliability)]
+    async fn scorecard_get(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Query(window): Query<Option<ScorecardWindow>>,
+    ) -> Result<Json<Vec<SystemScorecard>>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Score every System over the window
+        let scorecards: Vec<SystemScorecard> = scorecard_read(&mut tx, &window.unwrap_or_default())
+            .await
+            .map_err(http_error)?;
+
+        Ok(Json(scorecards))
+    }
+
+    /// Recent failed Dag Runs across all Systems, newest first, filtered by status and assignee
+    #[oai(path = "/failures", method = "get", tag = Tag::Failure)]
+    async fn failures_get(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
+        Query(status): Query<Option<FailureStatus>>,
+        Query(assignee): Query<Option<String>>,
+    ) -> Result<Json<Vec<Failure>>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Failures and who is looking after them
+        let failures: Vec<Failure> = failures_read(&mut tx, config, &status, &assignee)
+            .await
+            .map_err(http_error)?;
+
+        Ok(Json(failures))
+    }
+
+    /// Acknowledge, assign or resolve a failed Dag Run
+    #[oai(path = "/failures/:run_id/:action", method = "post", tag = Tag::Failure)]
+    async fn failure_post(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
+        Path(run_id): Path<String>,
+        Path(action): Path<FailureAction>,
+        Json(update): Json<FailureUpdate>,
+    ) -> Result<Json<Failure>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Look after the failure
+        let failure: Failure = failure_update(&mut tx, config, &run_id, &action, &update)
+            .await
+            .map_err(http_error)?;
+
+        // Commit Transaction
+        tx.commit().await.map_err(database_error)?;
+
+        Ok(Json(failure))
+    }
+
+    /// Notes left on a System, Dag Run or Task attempt, oldest first
+    #[oai(path = "/notes", method = "get", tag = Tag::Note)]
+    async fn notes_get(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
+        Query(system_id): Query<Option<String>>,
+        Query(run_id): Query<Option<String>>,
+        Query(task_id): Query<Option<String>>,
+        Query(attempt): Query<Option<u32>>,
+    ) -> Result<Json<Vec<Note>>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Notes from Kyubey and Airflow
+        let target = NoteTarget {
+            system_id,
+            run_id,
+            task_id,
+            attempt,
+        };
+        let notes: Vec<Note> = notes_read(&mut tx, config, &target)
+            .await
+            .map_err(http_error)?;
+
+        Ok(Json(notes))
+    }
+
+    /// Leave a markdown note on a System, Dag Run or Task attempt
+    #[oai(path = "/notes", method = "post", tag = Tag::Note)]
+    async fn notes_post(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
+        Json(new_note): Json<NewNote>,
+    ) -> Result<Json<Note>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Leave the note
+        let note: Note = note_create(&mut tx, config, &new_note)
+            .await
+            .map_err(http_error)?;
+
+        // Commit Transaction
+        tx.commit().await.map_err(database_error)?;
+
+        Ok(Json(note))
+    }
+
     /// Dag Run Details
     #[oai(path = "/dag_run/:run_id", method = "get", tag = Tag::DagRun)]
     async fn dag_run_get(
         &self,
         Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
         Path(run_id): Path<String>,
     ) -> Result<Json<DagRun>, poem::Error> {
         // Start Transaction
-        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(InternalServerError)?;
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
 
         // Dag Runs for a System
-        let dag_run: DagRun = dag_run_read(&mut tx, &run_id).await?;
+        let dag_run: DagRun = dag_run_read(&mut tx, config, &run_id)
+            .await
+            .map_err(http_error)?;
 
         Ok(Json(dag_run))
     }
 
+    /// Download every log for a Dag Run as a zip archive
+    #[oai(path = "/dag_run/:run_id/logs.zip", method = "get", tag = Tag::Log)]
+    async fn dag_run_logs_zip_get(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
+        Path(run_id): Path<String>,
+    ) -> Result<Attachment<Body>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Stream the archive as it is built
+        let bundle = log_bundle_read(&mut tx, config, &run_id, LogBundleFormat::Zip)
+            .await
+            .map_err(http_error)?;
+
+        Ok(Attachment::new(Body::from_bytes_stream(bundle))
+            .attachment_type(AttachmentType::Attachment)
+            .filename(format!("{}.zip", run_id)))
+    }
+
+    /// Download every log for a Dag Run as a gzipped tarball
+    #[oai(path = "/dag_run/:run_id/logs.tar.gz", method = "get", tag = Tag::Log)]
+    async fn dag_run_logs_tar_gz_get(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
+        Path(run_id): Path<String>,
+    ) -> Result<Attachment<Body>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Stream the archive as it is built
+        let bundle = log_bundle_read(&mut tx, config, &run_id, LogBundleFormat::TarGz)
+            .await
+            .map_err(http_error)?;
+
+        Ok(Attachment::new(Body::from_bytes_stream(bundle))
+            .attachment_type(AttachmentType::Attachment)
+            .filename(format!("{}.tar.gz", run_id)))
+    }
+
     /// Provide dag run and system details
     #[oai(path = "/dag_runs/:system_id", method = "get", tag = Tag::DagRun)]
     async fn dag_runs_for_system_get(
         &self,
         Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
         Path(system_id): Path<String>,
     ) -> Result<Json<SystemDagRuns>, poem::Error> {
         // Start Transaction
-        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(InternalServerError)?;
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
 
         // Dag Runs for a System
-        let dag_runs: SystemDagRuns = dag_runs_for_system_read(&mut tx, &system_id).await?;
+        let dag_runs: SystemDagRuns = dag_runs_for_system_read(&mut tx, config, &system_id)
+            .await
+            .map_err(http_error)?;
 
         Ok(Json(dag_runs))
     }
 
+    /// How many Dag Runs a System had per day, and the worst state they ended up in
+    #[oai(path = "/dag_runs/:system_id/calendar", method = "get", tag = Tag::DagRun)]
+    async fn dag_run_calendar_get(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Path(system_id): Path<String>,
+    ) -> Result<Json<Vec<CalendarDay>>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Dag Runs per day for a System
+        let calendar: Vec<CalendarDay> = system_calendar_read(&mut tx, &system_id)
+            .await
+            .map_err(http_error)?;
+
+        Ok(Json(calendar))
+    }
+
     /// Task Details
     #[oai(path = "/task/:run_id/:task_id", method = "get", tag = Tag::Task)]
     async fn task_get(
@@ -101,10 +443,12 @@ impl Api {
         Path(task_id): Path<String>,
     ) -> Result<Json<Task>, poem::Error> {
         // Start Transaction
-        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(InternalServerError)?;
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
 
         // Tasks for a Dag Runs
-        let task: Task = task_read(&mut tx, &run_id, &task_id).await?;
+        let task: Task = task_read(&mut tx, &run_id, &task_id)
+            .await
+            .map_err(http_error)?;
 
         Ok(Json(task))
     }
@@ -114,13 +458,16 @@ impl Api {
     async fn tasks_for_dag_run_get(
         &self,
         Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
         Path(run_id): Path<String>,
     ) -> Result<Json<DagRunTasks>, poem::Error> {
         // Start Transaction
-        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(InternalServerError)?;
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
 
         // Tasks for a Dag Runs
-        let tasks: DagRunTasks = tasks_for_dag_run_read(&mut tx, &run_id).await?;
+        let tasks: DagRunTasks = tasks_for_dag_run_read(&mut tx, config, &run_id)
+            .await
+            .map_err(http_error)?;
 
         Ok(Json(tasks))
     }
@@ -134,10 +481,229 @@ impl Api {
         Query(run_id): Query<String>,
         Query(task_id): Query<String>,
         Query(attempt): Query<u32>,
+        #[oai(name = "strip_ansi")] Query(plain): Query<Option<bool>>,
     ) -> Result<PlainText<String>, poem::Error> {
         // Log for a task attempt
-        let log: String = log_read(config, &dag_id, &run_id, &task_id, &attempt).await?;
+        let log: String = log_read(config, &dag_id, &run_id, &task_id, &attempt)
+            .await
+            .map_err(http_error)?;
+
+        // Remove ANSI escape codes if asked for plain text
+        let log: String = match plain {
+            Some(true) => strip_ansi(&log),
+            _ => log,
+        };
 
         Ok(PlainText(log))
     }
+
+    /// Provide the Log for a task, parsed into Airflow's log format.
+    /// Followers pass the last line number they have as `after_line`, and only get what is new.
+    #[allow(clippy::too_many_arguments)]
+    #[oai(path = "/log/structured", method = "get", tag = Tag::Log)]
+    async fn log_structured_get(
+        &self,
+        Data(config): Data<&Config>,
+        Query(dag_id): Query<String>,
+        Query(run_id): Query<String>,
+        Query(task_id): Query<String>,
+        Query(attempt): Query<u32>,
+        Query(min_level): Query<Option<LogLevel>>,
+        Query(after_line): Query<Option<u64>>,
+    ) -> Result<Json<Vec<LogLine>>, poem::Error> {
+        // Parsed log lines for a task attempt
+        let mut lines: Vec<LogLine> =
+            log_lines_read(config, &dag_id, &run_id, &task_id, &attempt, &min_level)
+                .await
+                .map_err(http_error)?;
+
+        // Skip what the caller already has
+        if let Some(after_line) = after_line {
+            lines.retain(|line: &LogLine| line.line_number > after_line);
+        }
+
+        Ok(Json(lines))
+    }
+
+    /// Compare the Logs of two attempts of a task, ignoring timestamps and PIDs
+    #[allow(clippy::too_many_arguments)]
+    #[oai(path = "/log/diff", method = "get", tag = Tag::Log)]
+    async fn log_diff_get(
+        &self,
+        Data(pool): Data<&PgPool>,
+        Data(config): Data<&Config>,
+        Query(dag_id): Query<String>,
+        Query(run_id): Query<String>,
+        Query(task_id): Query<String>,
+        Query(left_attempt): Query<u32>,
+        Query(right_attempt): Query<u32>,
+    ) -> Result<Json<LogDiff>, poem::Error> {
+        // Start Transaction
+        let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+        // Pull task details so we know which attempts exist
+        let task: Task = task_read(&mut tx, &run_id, &task_id)
+            .await
+            .map_err(http_error)?;
+        let try_number: u32 = match task.try_number {
+            Some(try_number) if try_number > 0 => Ok(try_number),
+            _ => Err(KyubeyError::NoLogs {
+                id: format!("{}/{}", run_id, task_id),
+            }),
+        }
+        .map_err(http_error)?;
+
+        // Side by side diff of both attempts
+        let diff: LogDiff = log_diff_read(
+            config,
+            &dag_id,
+            &run_id,
+            &task_id,
+            &left_attempt,
+            &right_attempt,
+            &try_number,
+        )
+        .await
+        .map_err(http_error)?;
+
+        Ok(Json(diff))
+    }
+}
+
+/// HTTP status for each kind of Kyubey error
+pub fn status(err: &KyubeyError) -> StatusCode {
+    match err {
+        KyubeyError::NotFound { .. }
+        | KyubeyError::NoParentSystem { .. }
+        | KyubeyError::NoLogs { .. } => StatusCode::NOT_FOUND,
+        KyubeyError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+        KyubeyError::LogUnavailable { source, .. } if source.kind() == ErrorKind::NotFound => {
+            StatusCode::NOT_FOUND
+        }
+        KyubeyError::LogUnavailable { .. } | KyubeyError::Backend(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+        KyubeyError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+/// Wrap a Kyubey error with its HTTP status, so handlers can hand it to Poem
+pub fn http_error(err: KyubeyError) -> poem::Error {
+    let status: StatusCode = status(&err);
+    poem::Error::new(err, status)
+}
+
+/// Wrap a database failure the same way
+pub fn database_error(err: sqlx::Error) -> poem::Error {
+    http_error(KyubeyError::Backend(err))
+}
+
+/// What a not found problem was about, as the API spells it
+#[derive(Enum)]
+#[oai(rename_all = "snake_case")]
+enum ProblemEntity {
+    System,
+    DagRun,
+    Task,
+    FailedDagRun,
+    SavedSearch,
+}
+
+impl From<Entity> for ProblemEntity {
+    fn from(entity: Entity) -> Self {
+        match entity {
+            Entity::System => ProblemEntity::System,
+            Entity::DagRun => ProblemEntity::DagRun,
+            Entity::Task => ProblemEntity::Task,
+            Entity::FailedDagRun => ProblemEntity::FailedDagRun,
+            Entity::SavedSearch => ProblemEntity::SavedSearch,
+        }
+    }
+}
+
+/// Error body for the API, following RFC 9457 problem details
+#[derive(Object)]
+struct ProblemDetails {
+    #[oai(rename = "type")]
+    problem_type: String,
+    title: String,
+    status: u16,
+    detail: String,
+    #[oai(skip_serializing_if_is_none)]
+    entity: Option<ProblemEntity>,
+    #[oai(skip_serializing_if_is_none)]
+    id: Option<String>,
+}
+
+/// Render any error from the API as problem details, so clients get JSON instead of plain text
+pub async fn problem_details(err: poem::Error) -> Response {
+    let status: StatusCode = err.status();
+
+    // Not found errors from Kyubey say what was missing
+    let (entity, id) = match err.downcast_ref::<KyubeyError>() {
+        Some(KyubeyError::NotFound { entity, id }) => (Some(*entity), Some(id.clone())),
+        Some(KyubeyError::NoParentSystem { run_id }) => {
+            (Some(Entity::DagRun), Some(run_id.clone()))
+        }
+        Some(KyubeyError::NoLogs { id }) => (Some(Entity::Task), Some(id.clone())),
+        _ => (None, None),
+    };
+
+    // Say what went wrong, without leaking internals when the fault is ours.
+    // A switched off feature is on purpose, so it still says why.
+    let detail: String = match err.downcast_ref::<KyubeyError>() {
+        Some(KyubeyError::Unavailable(message)) => message.clone(),
+        _ if status.is_server_error() => {
+            tracing::error!("Unable to answer API request: {err}");
+            "Something went wrong on our side. If it keeps happening, reach out to support with the x-request-id header of this response.".to_string()
+        }
+        _ => err.to_string(),
+    };
+
+    let problem = ProblemDetails {
+        problem_type: "about:blank".to_string(),
+        title: status.canonical_reason().unwrap_or_default().to_string(),
+        status: status.as_u16(),
+        detail,
+        entity: entity.map(ProblemEntity::from),
+        id,
+    };
+
+    Response::builder()
+        .status(status)
+        .content_type("application/problem+json")
+        .body(problem.to_json_string())
+}
+
+/// Body for the liveness probe
+#[derive(Object)]
+struct Health {
+    status: String,
+}
+
+/// Liveness probe, answers as long as the process is up without touching any dependency
+#[handler]
+pub async fn healthz() -> Response {
+    let health = Health {
+        status: "ok".to_string(),
+    };
+
+    Response::builder()
+        .content_type("application/json")
+        .body(health.to_json_string())
+}
+
+/// Readiness probe, checks each environment's database, logs and schema
+#[handler]
+pub async fn readyz(Data(environments): Data<&Vec<Environment>>) -> Response {
+    let readiness: Readiness = readiness_read(environments).await;
+    let status: StatusCode = match readiness.ready {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    Response::builder()
+        .status(status)
+        .content_type("application/json")
+        .body(readiness.to_json_string())
 }
@@ -0,0 +1,430 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use color_eyre::eyre;
+use kyubey::{
+    DEFAULT_ENVIRONMENT,
+    core::{
+        DagRun, DagRunTasks, EnvironmentSearch, EnvironmentSystem, EnvironmentWarning, LogLevel,
+        LogLine, System, SystemDagRuns, Task,
+    },
+};
+use poem_openapi::types::{ParseFromJSON, ToJSON};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// How often we check for new log lines when following a log
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Talk to Kyubey from the terminal
+#[derive(Parser)]
+#[command(name = "kyubey-cli", version)]
+struct Cli {
+    /// Where Kyubey is running
+    #[arg(long, env = "KYUBEY_URL", default_value = "http://localhost:3000")]
+    url: String,
+
+    /// Airflow environment to ask about
+    #[arg(long, env = "KYUBEY_ENVIRONMENT", default_value = DEFAULT_ENVIRONMENT)]
+    environment: String,
+
+    /// How to print results
+    #[arg(long, value_enum, default_value_t = Output::Table)]
+    output: Output,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// How to print results
+#[derive(Clone, Copy, ValueEnum)]
+enum Output {
+    Table,
+    Json,
+}
+
+/// What we can ask Kyubey for
+#[derive(Subcommand)]
+enum Command {
+    /// Search for your System in every environment
+    Search {
+        /// Client or System name or ID to search by
+        search_by: String,
+        /// Page of results, starting at 0
+        #[arg(long, default_value_t = 0)]
+        page: u32,
+    },
+    /// List the Dag Runs for a System
+    DagRuns {
+        /// System ID to list Dag Runs for
+        system_id: String,
+    },
+    /// Show the Tasks of a Dag Run
+    Tasks {
+        /// Run ID to show Tasks for
+        run_id: String,
+    },
+    /// Print the log for a Task attempt
+    Logs {
+        /// Run ID the Task belongs to
+        run_id: String,
+        /// Task ID to print the log for
+        task_id: String,
+        /// Attempt to print, defaults to the latest
+        #[arg(long)]
+        attempt: Option<u32>,
+        /// Only print lines at or above this level
+        #[arg(long, value_parser = parse_log_level)]
+        min_level: Option<LogLevel>,
+        /// Keep printing new lines until the Task finishes
+        #[arg(long, short)]
+        follow: bool,
+    },
+}
+
+/// Read a log level the way Airflow writes it
+fn parse_log_level(text: &str) -> Result<LogLevel, String> {
+    text.to_uppercase()
+        .parse()
+        .map_err(|_| format!("unknown log level: {text}"))
+}
+
+/// Client for Kyubey's REST API
+struct Client {
+    http: reqwest::Client,
+    url: String,
+    environment: String,
+}
+
+impl Client {
+    /// Call a Kyubey endpoint and parse the JSON it returns
+    async fn get<T: ParseFromJSON>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, eyre::Error> {
+        let response = self
+            .http
+            .get(format!(
+                "{}/api/{}{}",
+                self.url.trim_end_matches('/'),
+                self.environment,
+                path
+            ))
+            .query(query)
+            .send()
+            .await?;
+
+        // Surface Kyubey's own error message
+        let status = response.status();
+        let body: String = response.text().await?;
+        if !status.is_success() {
+            eyre::bail!("Kyubey returned {status}: {body}");
+        }
+
+        T::parse_from_json_string(&body).map_err(|err| eyre::eyre!("{}", err.message()))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), eyre::Error> {
+    // Lets get pretty error reports
+    color_eyre::install()?;
+
+    let cli = Cli::parse();
+    let client = Client {
+        http: reqwest::Client::new(),
+        url: cli.url,
+        environment: cli.environment,
+    };
+
+    match cli.command {
+        Command::Search { search_by, page } => {
+            let search: EnvironmentSearch = client
+                .get(
+                    "/search_environments",
+                    &[("search_by", search_by), ("page", page.to_string())],
+                )
+                .await?;
+            print_systems(&cli.output, &search);
+        }
+        Command::DagRuns { system_id } => {
+            let dag_runs: SystemDagRuns =
+                client.get(&format!("/dag_runs/{system_id}"), &[]).await?;
+            print_dag_runs(&cli.output, &dag_runs);
+        }
+        Command::Tasks { run_id } => {
+            let tasks: DagRunTasks = client.get(&format!("/tasks/{run_id}"), &[]).await?;
+            print_tasks(&cli.output, &tasks);
+        }
+        Command::Logs {
+            run_id,
+            task_id,
+            attempt,
+            min_level,
+            follow,
+        } => {
+            print_logs(
+                &client,
+                &cli.output,
+                &run_id,
+                &task_id,
+                attempt,
+                &min_level,
+                follow,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Print Systems found by a search, and which environments it couldn't reach
+fn print_systems(output: &Output, search: &EnvironmentSearch) {
+    match output {
+        Output::Json => println!("{}", search.to_json_string()),
+        Output::Table => {
+            for EnvironmentWarning {
+                environment,
+                message,
+            } in &search.warnings
+            {
+                eprintln!("Couldn't search {environment}: {message}");
+            }
+            print_table(
+                &[
+                    "Environment",
+                    "Client Name",
+                    "Client ID",
+                    "System Name",
+                    "System ID",
+                    "Latest Run",
+                    "Dag Runs",
+                ],
+                search
+                    .systems
+                    .iter()
+                    .map(
+                        |EnvironmentSystem {
+                             environment,
+                             system,
+                         }| {
+                            vec![
+                                environment.clone(),
+                                system.client_name.clone(),
+                                system.client_id.clone(),
+                                system.system_name.clone(),
+                                system.system_id.clone(),
+                                system.latest_run.to_string(),
+                                system.number_of_dag_runs.to_string(),
+                            ]
+                        },
+                    )
+                    .collect(),
+            );
+        }
+    }
+}
+
+/// Print the Dag Runs of a System
+fn print_dag_runs(output: &Output, dag_runs: &SystemDagRuns) {
+    match output {
+        Output::Json => println!("{}", dag_runs.to_json_string()),
+        Output::Table => {
+            let system: &System = &dag_runs.system;
+            println!("{} / {}\n", system.client_name, system.system_name);
+            print_table(
+                &[
+                    "Dag ID",
+                    "Run ID",
+                    "State",
+                    "Execution Date",
+                    "Start",
+                    "End",
+                ],
+                dag_runs
+                    .dag_runs
+                    .iter()
+                    .map(|dag_run: &DagRun| {
+                        vec![
+                            dag_run.dag_id.clone(),
+                            dag_run.run_id.clone(),
+                            optional(&dag_run.state),
+                            dag_run.execution_date.to_string(),
+                            optional(&dag_run.start_date),
+                            optional(&dag_run.end_date),
+                        ]
+                    })
+                    .collect(),
+            );
+        }
+    }
+}
+
+/// Print the Tasks of a Dag Run
+fn print_tasks(output: &Output, tasks: &DagRunTasks) {
+    match output {
+        Output::Json => println!("{}", tasks.to_json_string()),
+        Output::Table => {
+            let dag_run: &DagRun = &tasks.dag_run;
+            println!(
+                "{} {} {}\n",
+                dag_run.dag_id,
+                dag_run.run_id,
+                optional(&dag_run.state)
+            );
+            print_table(
+                &["Task ID", "State", "Start", "End", "Tries"],
+                tasks
+                    .tasks
+                    .iter()
+                    .map(|task: &Task| {
+                        vec![
+                            task.task_id.clone(),
+                            optional(&task.state),
+                            optional(&task.start_date),
+                            optional(&task.end_date),
+                            optional(&task.try_number),
+                        ]
+                    })
+                    .collect(),
+            );
+        }
+    }
+}
+
+/// Print the log for a Task attempt, and keep printing new lines when following
+async fn print_logs(
+    client: &Client,
+    output: &Output,
+    run_id: &str,
+    task_id: &str,
+    attempt: Option<u32>,
+    min_level: &Option<LogLevel>,
+    follow: bool,
+) -> Result<(), eyre::Error> {
+    // Logs are found by Dag ID, so look up the Dag Run first
+    let dag_run: DagRun = client.get(&format!("/dag_run/{run_id}"), &[]).await?;
+    let mut task: Task = client
+        .get(&format!("/task/{run_id}/{task_id}"), &[])
+        .await?;
+
+    // Without an attempt we stay on the latest one, moving on to retries as they start
+    let latest: bool = attempt.is_none();
+    let mut attempt: u32 = attempt
+        .or(task.try_number.filter(|try_number: &u32| *try_number > 0))
+        .unwrap_or(1);
+    let query = |attempt: u32| -> Vec<(&str, String)> {
+        let mut query: Vec<(&str, String)> = vec![
+            ("dag_id", dag_run.dag_id.clone()),
+            ("run_id", run_id.to_string()),
+            ("task_id", task_id.to_string()),
+            ("attempt", attempt.to_string()),
+        ];
+        if let Some(min_level) = min_level {
+            query.push(("min_level", min_level.to_string()));
+        }
+        query
+    };
+
+    let mut printed: u64 = 0;
+    loop {
+        // A Task without a state has not been scheduled yet, so it may still write a log
+        let finished: bool = task.state.is_some_and(|state| state.is_terminal());
+
+        match print_log_lines(client, output, &query(attempt), printed).await {
+            Ok(last) => printed = last,
+            // A Task that has not started has no log yet, so keep waiting for one
+            Err(_) if follow && !finished => {}
+            Err(err) => return Err(err),
+        }
+
+        // Stop once the Task is done and nothing more can be written
+        if !follow || finished {
+            return Ok(());
+        }
+
+        sleep(FOLLOW_INTERVAL).await;
+        task = client
+            .get(&format!("/task/{run_id}/{task_id}"), &[])
+            .await?;
+
+        // A retry started, finish the attempt we were on and start the new one from the top
+        if let Some(try_number) = task
+            .try_number
+            .filter(|try_number: &u32| latest && *try_number > attempt)
+        {
+            print_log_lines(client, output, &query(attempt), printed)
+                .await
+                .ok();
+            eprintln!("--- attempt {try_number} ---");
+            attempt = try_number;
+            printed = 0;
+        }
+    }
+}
+
+/// Print the lines of a log after the ones already printed, returning the last line printed
+async fn print_log_lines(
+    client: &Client,
+    output: &Output,
+    query: &[(&str, String)],
+    printed: u64,
+) -> Result<u64, eyre::Error> {
+    // Only ask for what we have not printed yet
+    let mut after: Vec<(&str, String)> = query.to_vec();
+    after.push(("after_line", printed.to_string()));
+    let lines: Vec<LogLine> = client.get("/log/structured", &after).await?;
+
+    let mut printed: u64 = printed;
+    for line in lines {
+        match output {
+            Output::Json => println!("{}", line.to_json_string()),
+            Output::Table => println!("{}", line.text),
+        }
+        printed = printed.max(line.line_number);
+    }
+
+    Ok(printed)
+}
+
+/// Show a value that might be missing, leaving the cell blank
+fn optional<T: ToString>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map(|value: &T| value.to_string())
+        .unwrap_or_default()
+}
+
+/// Print rows as a plain text table, with each column padded to its widest cell
+fn print_table(headers: &[&str], rows: Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = headers.iter().map(|header: &&str| header.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: Vec<String>| -> String {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!(
+        "{}",
+        format_row(
+            headers
+                .iter()
+                .map(|header: &&str| header.to_string())
+                .collect()
+        )
+    );
+    for row in rows {
+        println!("{}", format_row(row));
+    }
+}
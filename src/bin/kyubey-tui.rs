@@ -0,0 +1,740 @@
+use clap::Parser;
+use color_eyre::eyre;
+use kyubey::{
+    Config,
+    core::{
+        DagRun, DagRunTasks, DagState, LogLevel, LogLine, Note, SlaStatus, System, SystemDagRuns,
+        Task, TaskState, dag_runs_for_system_read, log_lines_read, search_systems_read,
+        tasks_for_dag_run_read,
+    },
+    db::{
+        airflow_notes_exist_select, airflow_task_fail_exists_select, kyubey_schema_exists_select,
+    },
+    settings::{Settings, SettingsArgs},
+};
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Row, Table, TableState},
+};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::time::{Duration, Instant};
+
+/// How often the screen reloads itself, so running Dag Runs and Tasks stay current
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long we wait on a key press before checking if it is time to refresh
+const KEY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Browse Airflow from the terminal
+#[derive(Parser)]
+#[command(name = "kyubey-tui", version)]
+struct Cli {
+    /// Airflow environment to browse, defaults to the first one in the settings
+    #[arg(long, env = "KYUBEY_ENVIRONMENT")]
+    environment: Option<String>,
+
+    #[command(flatten)]
+    settings: SettingsArgs,
+}
+
+/// One screen of the dashboard, following the web UI from search down to logs
+enum View {
+    Search {
+        search_by: String,
+        systems: Vec<System>,
+        selected: TableState,
+    },
+    DagRuns {
+        system_id: String,
+        dag_runs: Option<SystemDagRuns>,
+        selected: TableState,
+    },
+    Tasks {
+        run_id: String,
+        tasks: Option<DagRunTasks>,
+        selected: TableState,
+    },
+    Logs {
+        dag_id: String,
+        run_id: String,
+        task_id: String,
+        attempt: u32,
+        try_number: u32,
+        lines: Vec<LogLine>,
+        scroll: usize,
+    },
+}
+
+/// Everything the dashboard needs to draw itself and reload
+struct App {
+    config: Config,
+    pool: PgPool,
+    views: Vec<View>,
+    error: Option<String>,
+    refreshed: Instant,
+    quit: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), eyre::Error> {
+    // Lets get pretty error reports
+    color_eyre::install()?;
+
+    // Same settings as the web server, for whichever environment we were asked to browse
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+    let settings: Settings = Settings::load(cli.settings)?;
+    let mut configs: Vec<Config> = settings.configs()?;
+    let index: usize = match &cli.environment {
+        Some(environment) => configs
+            .iter()
+            .position(|config: &Config| config.environment == *environment)
+            .ok_or_else(|| eyre::eyre!("No environment named {environment} in the settings"))?,
+        None => 0,
+    };
+    let mut config: Config = configs.swap_remove(index);
+
+    // Connect to PostgreSQL
+    let pool = PgPool::connect(&config.database_url).await?;
+
+    // Read what the web server keeps in Kyubey's schema, but leave setting it up to the server
+    config.kyubey_schema =
+        settings.kyubey_schema.unwrap_or(true) && kyubey_schema_exists_select(&pool).await?;
+    config.airflow_notes = airflow_notes_exist_select(&pool).await?;
+    config.airflow_task_fail = airflow_task_fail_exists_select(&pool).await?;
+
+    let mut app = App {
+        config,
+        pool,
+        views: vec![View::Search {
+            search_by: String::new(),
+            systems: Vec::new(),
+            selected: TableState::default(),
+        }],
+        error: None,
+        refreshed: Instant::now(),
+        quit: false,
+    };
+    app.reload().await;
+
+    // Take over the terminal, handing it back even if we fail
+    let terminal: DefaultTerminal = ratatui::init();
+    let result: Result<(), eyre::Error> = app.run(terminal).await;
+    ratatui::restore();
+
+    result
+}
+
+impl App {
+    /// Draw, handle keys and refresh until asked to quit
+    async fn run(&mut self, mut terminal: DefaultTerminal) -> Result<(), eyre::Error> {
+        while !self.quit {
+            terminal.draw(|frame: &mut Frame| self.render(frame))?;
+
+            if event::poll(KEY_POLL_INTERVAL)? {
+                if let Event::Key(key) = event::read()?
+                    && key.kind == KeyEventKind::Press
+                {
+                    self.handle_key(key).await;
+                }
+            } else if self.refreshed.elapsed() >= REFRESH_INTERVAL {
+                self.reload().await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Screen currently on top
+    fn view(&mut self) -> &mut View {
+        self.views.last_mut().expect("search view is never popped")
+    }
+
+    /// Reload the current screen, keeping any error for the status line
+    async fn reload(&mut self) {
+        self.error = self.load().await.err().map(|err| err.to_string());
+        self.refreshed = Instant::now();
+    }
+
+    /// Pull the data for the current screen with the same reads the web UI uses
+    async fn load(&mut self) -> Result<(), eyre::Error> {
+        let config: Config = self.config.clone();
+        let mut tx: Transaction<'_, Postgres> = self.pool.begin().await?;
+
+        match self.view() {
+            View::Search {
+                search_by,
+                systems,
+                selected,
+            } => {
+                *systems = search_systems_read(&mut tx, &config, search_by, &0).await?;
+                keep_selection(selected, systems.len());
+            }
+            View::DagRuns {
+                system_id,
+                dag_runs,
+                selected,
+            } => {
+                let loaded: SystemDagRuns =
+                    dag_runs_for_system_read(&mut tx, &config, system_id).await?;
+                keep_selection(selected, loaded.dag_runs.len());
+                *dag_runs = Some(loaded);
+            }
+            View::Tasks {
+                run_id,
+                tasks,
+                selected,
+            } => {
+                let loaded: DagRunTasks = tasks_for_dag_run_read(&mut tx, &config, run_id).await?;
+                keep_selection(selected, loaded.tasks.len());
+                *tasks = Some(loaded);
+            }
+            View::Logs {
+                dag_id,
+                run_id,
+                task_id,
+                attempt,
+                lines,
+                ..
+            } => {
+                *lines = log_lines_read(&config, dag_id, run_id, task_id, attempt, &None).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Move around the dashboard with the keyboard
+    async fn handle_key(&mut self, key: KeyEvent) {
+        // Always let people out
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+
+        // The search box takes every other key while typing
+        if let View::Search { search_by, .. } = self.view() {
+            match key.code {
+                KeyCode::Char(letter) => {
+                    search_by.push(letter);
+                    self.reload().await;
+                    return;
+                }
+                KeyCode::Backspace => {
+                    search_by.pop();
+                    self.reload().await;
+                    return;
+                }
+                KeyCode::Esc => {
+                    self.quit = true;
+                    return;
+                }
+                _ => (),
+            }
+        }
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Esc | KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h')
+                if self.views.len() > 1 =>
+            {
+                self.views.pop();
+                self.reload().await;
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => self.open().await,
+            KeyCode::Up | KeyCode::Char('k') => self.step(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.step(1),
+            KeyCode::PageUp => self.step(-20),
+            KeyCode::PageDown => self.step(20),
+            KeyCode::Char('[') => self.change_attempt(-1).await,
+            KeyCode::Char(']') => self.change_attempt(1).await,
+            KeyCode::Char('r') => self.reload().await,
+            _ => (),
+        }
+    }
+
+    /// Move the selection, or scroll the log
+    fn step(&mut self, by: i32) {
+        match self.view() {
+            View::Search {
+                systems, selected, ..
+            } => move_selection(selected, systems.len(), by),
+            View::DagRuns {
+                dag_runs, selected, ..
+            } => {
+                let count: usize = dag_runs.as_ref().map_or(0, |runs| runs.dag_runs.len());
+                move_selection(selected, count, by);
+            }
+            View::Tasks {
+                tasks, selected, ..
+            } => {
+                let count: usize = tasks.as_ref().map_or(0, |tasks| tasks.tasks.len());
+                move_selection(selected, count, by);
+            }
+            View::Logs { lines, scroll, .. } => {
+                // Kept as a line index, so logs longer than the terminal can count still scroll
+                let last: usize = lines.len().saturating_sub(1);
+                let distance: usize = by.unsigned_abs() as usize;
+                *scroll = match by < 0 {
+                    true => scroll.saturating_sub(distance),
+                    false => scroll.saturating_add(distance).min(last),
+                };
+            }
+        }
+    }
+
+    /// Drill into whatever is selected, just like following a link in the web UI
+    async fn open(&mut self) {
+        let next: Option<View> = match self.view() {
+            View::Search {
+                systems, selected, ..
+            } => selected
+                .selected()
+                .and_then(|index: usize| systems.get(index))
+                .map(|system: &System| View::DagRuns {
+                    system_id: system.system_id.clone(),
+                    dag_runs: None,
+                    selected: TableState::default(),
+                }),
+            View::DagRuns {
+                dag_runs, selected, ..
+            } => selected
+                .selected()
+                .zip(dag_runs.as_ref())
+                .and_then(|(index, runs)| runs.dag_runs.get(index))
+                .map(|dag_run: &DagRun| View::Tasks {
+                    run_id: dag_run.run_id.clone(),
+                    tasks: None,
+                    selected: TableState::default(),
+                }),
+            View::Tasks {
+                tasks, selected, ..
+            } => selected
+                .selected()
+                .zip(tasks.as_ref())
+                .and_then(|(index, tasks)| {
+                    tasks
+                        .tasks
+                        .get(index)
+                        .map(|task: &Task| (&tasks.dag_run, task))
+                })
+                .map(|(dag_run, task)| {
+                    let try_number: u32 = task.try_number.unwrap_or(1).max(1);
+                    View::Logs {
+                        dag_id: dag_run.dag_id.clone(),
+                        run_id: task.run_id.clone(),
+                        task_id: task.task_id.clone(),
+                        attempt: try_number,
+                        try_number,
+                        lines: Vec::new(),
+                        scroll: 0,
+                    }
+                }),
+            View::Logs { .. } => None,
+        };
+
+        if let Some(next) = next {
+            self.views.push(next);
+            self.reload().await;
+        }
+    }
+
+    /// Switch to an earlier or later attempt of a Task
+    async fn change_attempt(&mut self, by: i32) {
+        if let View::Logs {
+            attempt,
+            try_number,
+            scroll,
+            ..
+        } = self.view()
+        {
+            let next: u32 = (*attempt as i32 + by).clamp(1, *try_number as i32) as u32;
+            if next != *attempt {
+                *attempt = next;
+                *scroll = 0;
+                self.reload().await;
+            }
+        }
+    }
+
+    /// Draw the current screen with its breadcrumbs and key hints
+    fn render(&mut self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        // Breadcrumbs, like the web UI header
+        frame.render_widget(Paragraph::new(self.breadcrumbs()), header);
+
+        // Errors win the status line, otherwise show what the keys do
+        let status: Line = match &self.error {
+            Some(error) => Line::from(error.as_str()).red(),
+            None => Line::from(match self.views.last() {
+                Some(View::Search { .. }) => "type to search  ↑↓ select  enter open  esc quit",
+                Some(View::Logs { .. }) => {
+                    "↑↓ pgup pgdn scroll  [ ] attempt  r refresh  esc back  q quit"
+                }
+                _ => "↑↓ select  enter open  r refresh  esc back  q quit",
+            })
+            .dim(),
+        };
+        frame.render_widget(Paragraph::new(status), footer);
+
+        match self.view() {
+            View::Search {
+                search_by,
+                systems,
+                selected,
+            } => render_search(frame, body, search_by, systems, selected),
+            View::DagRuns {
+                dag_runs, selected, ..
+            } => render_dag_runs(frame, body, dag_runs, selected),
+            View::Tasks {
+                tasks, selected, ..
+            } => render_tasks(frame, body, tasks, selected),
+            View::Logs {
+                task_id,
+                attempt,
+                try_number,
+                lines,
+                scroll,
+                ..
+            } => render_logs(frame, body, task_id, attempt, try_number, lines, scroll),
+        }
+    }
+
+    /// Where we are, from search down to the current screen
+    fn breadcrumbs(&self) -> Line<'static> {
+        let mut crumbs: Vec<Span> = vec![Span::from("Kyubey").bold()];
+        for view in &self.views {
+            let crumb: Option<String> = match view {
+                View::Search { .. } => None,
+                View::DagRuns { system_id, .. } => Some(system_id.clone()),
+                View::Tasks { run_id, .. } => Some(run_id.clone()),
+                View::Logs {
+                    task_id, attempt, ..
+                } => Some(format!("{task_id} (attempt {attempt})")),
+            };
+            if let Some(crumb) = crumb {
+                crumbs.push(Span::from(" › ").dim());
+                crumbs.push(Span::from(crumb));
+            }
+        }
+        Line::from(crumbs)
+    }
+}
+
+/// Keep the selection on the screen when the list changes size
+fn keep_selection(selected: &mut TableState, count: usize) {
+    match (selected.selected(), count) {
+        (_, 0) => selected.select(None),
+        (None, _) => selected.select(Some(0)),
+        (Some(index), _) if index >= count => selected.select(Some(count - 1)),
+        _ => (),
+    }
+}
+
+/// Move the selection up or down, stopping at either end
+fn move_selection(selected: &mut TableState, count: usize, by: i32) {
+    if count == 0 {
+        return;
+    }
+    let index: i32 = selected.selected().unwrap_or(0) as i32 + by;
+    selected.select(Some(index.clamp(0, count as i32 - 1) as usize));
+}
+
+/// Same colors as the web badges in `ui::util::dag_state_badge_type`
+fn dag_state_color(state: &DagState) -> Color {
+    match state {
+        DagState::Failed => Color::Red,
+        DagState::Queued => Color::Gray,
+        DagState::Running => Color::Blue,
+        DagState::Success => Color::Green,
+    }
+}
+
+/// Same colors as the web badges in `ui::util::sla_status_badge_type`
+fn sla_status_color(status: &SlaStatus) -> Color {
+    match status {
+        SlaStatus::Breached => Color::Red,
+        SlaStatus::Met => Color::Green,
+        SlaStatus::Pending => Color::Gray,
+    }
+}
+
+/// Same colors as the web badges in `ui::util::task_state_badge_type`
+fn task_state_color(state: &TaskState) -> Color {
+    match state {
+        TaskState::Deferred => Color::Cyan,
+        TaskState::Failed => Color::Red,
+        TaskState::Queued => Color::Gray,
+        TaskState::Removed => Color::Gray,
+        TaskState::Restarting => Color::Magenta,
+        TaskState::Running => Color::Blue,
+        TaskState::Scheduled => Color::Gray,
+        TaskState::Skipped => Color::Gray,
+        TaskState::Success => Color::Green,
+        TaskState::UpForReschedule => Color::Yellow,
+        TaskState::UpForRetry => Color::Yellow,
+        TaskState::UpstreamFailed => Color::Yellow,
+    }
+}
+
+/// Same colors as the web log viewer in `ui::util::log_level_text_type`
+fn log_level_style(level: &LogLevel) -> Style {
+    match level {
+        LogLevel::Debug => Style::new().dim(),
+        LogLevel::Info => Style::new(),
+        LogLevel::Warning => Style::new().yellow(),
+        LogLevel::Error => Style::new().red(),
+        LogLevel::Critical => Style::new().red().add_modifier(Modifier::BOLD),
+    }
+}
+
+/// Show a value that might be missing, leaving the cell blank
+fn optional<T: ToString>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map(|value: &T| value.to_string())
+        .unwrap_or_default()
+}
+
+/// Style for the selected row of every table
+fn selected_style() -> Style {
+    Style::new().reversed()
+}
+
+/// Search box and the Systems it found
+fn render_search(
+    frame: &mut Frame,
+    area: Rect,
+    search_by: &str,
+    systems: &[System],
+    selected: &mut TableState,
+) {
+    let [search, results] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+
+    frame.render_widget(
+        Paragraph::new(format!("{search_by}▏")).block(Block::bordered().title("Search Systems")),
+        search,
+    );
+
+    let rows: Vec<Row> = systems
+        .iter()
+        .map(|system: &System| {
+            Row::new(vec![
+                system.client_name.clone(),
+                system.client_id.clone(),
+                system.system_name.clone(),
+                system.system_id.clone(),
+                system.latest_run.to_string(),
+                system.number_of_dag_runs.to_string(),
+            ])
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(2),
+            Constraint::Fill(1),
+            Constraint::Fill(2),
+            Constraint::Fill(1),
+            Constraint::Length(31),
+            Constraint::Length(9),
+        ],
+    )
+    .header(
+        Row::new([
+            "Client Name",
+            "Client ID",
+            "System Name",
+            "System ID",
+            "Latest Run",
+            "Dag Runs",
+        ])
+        .bold(),
+    )
+    .row_highlight_style(selected_style());
+    frame.render_stateful_widget(table, results, selected);
+}
+
+/// Dag Runs for a System
+fn render_dag_runs(
+    frame: &mut Frame,
+    area: Rect,
+    dag_runs: &Option<SystemDagRuns>,
+    selected: &mut TableState,
+) {
+    let Some(dag_runs) = dag_runs else {
+        return;
+    };
+    let system: &System = &dag_runs.system;
+
+    let rows: Vec<Row> = dag_runs
+        .dag_runs
+        .iter()
+        .map(|dag_run: &DagRun| {
+            let state: Span = match &dag_run.state {
+                Some(state) => Span::from(state.to_string()).fg(dag_state_color(state)),
+                None => Span::from(""),
+            };
+            let sla: Span = match &dag_run.sla_status {
+                Some(status) => Span::from(status.to_string()).fg(sla_status_color(status)),
+                None => Span::from(""),
+            };
+            Row::new(vec![
+                Line::from(dag_run.dag_id.clone()),
+                Line::from(dag_run.run_id.clone()),
+                Line::from(state),
+                Line::from(sla),
+                Line::from(dag_run.execution_date.to_string()),
+                Line::from(optional(&dag_run.start_date)),
+                Line::from(optional(&dag_run.end_date)),
+            ])
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(1),
+            Constraint::Fill(2),
+            Constraint::Length(8),
+            Constraint::Length(12),
+            Constraint::Length(31),
+            Constraint::Length(31),
+            Constraint::Length(31),
+        ],
+    )
+    .header(
+        Row::new([
+            "Dag ID",
+            "Run ID",
+            "State",
+            "SLA",
+            "Execution Date",
+            "Start",
+            "End",
+        ])
+        .bold(),
+    )
+    .row_highlight_style(selected_style())
+    .block(Block::bordered().title(format!("{} / {}", system.client_name, system.system_name)));
+    frame.render_stateful_widget(table, area, selected);
+}
+
+/// Tasks for a Dag Run
+fn render_tasks(
+    frame: &mut Frame,
+    area: Rect,
+    tasks: &Option<DagRunTasks>,
+    selected: &mut TableState,
+) {
+    let Some(tasks) = tasks else {
+        return;
+    };
+    let dag_run: &DagRun = &tasks.dag_run;
+
+    let rows: Vec<Row> = tasks
+        .tasks
+        .iter()
+        .map(|task: &Task| {
+            let state: Span = match &task.state {
+                Some(state) => Span::from(state.to_string()).fg(task_state_color(state)),
+                None => Span::from(""),
+            };
+            Row::new(vec![
+                Line::from(task.task_id.clone()),
+                Line::from(state),
+                Line::from(optional(&task.start_date)),
+                Line::from(optional(&task.end_date)),
+                Line::from(optional(&task.try_number)),
+            ])
+        })
+        .collect();
+    let mut title: Vec<Span> = vec![Span::from(format!(" {} ", dag_run.dag_id))];
+    if let Some(state) = &dag_run.state {
+        title.push(Span::from(state.to_string()).fg(dag_state_color(state)));
+        title.push(Span::from(" "));
+    }
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(1),
+            Constraint::Length(17),
+            Constraint::Length(31),
+            Constraint::Length(31),
+            Constraint::Length(5),
+        ],
+    )
+    .header(Row::new(["Task ID", "State", "Start", "End", "Tries"]).bold())
+    .row_highlight_style(selected_style())
+    .block(Block::bordered().title(Line::from(title)));
+
+    // Notes left on the Dag Run sit under its Tasks, when there are any
+    if tasks.notes.is_empty() {
+        frame.render_stateful_widget(table, area, selected);
+        return;
+    }
+    let notes_height: u16 = u16::try_from(tasks.notes.len() + 2)
+        .unwrap_or(u16::MAX)
+        .min(area.height / 3);
+    let [tasks_area, notes_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(notes_height)]).areas(area);
+    frame.render_stateful_widget(table, tasks_area, selected);
+
+    let notes: Vec<Line> = tasks
+        .notes
+        .iter()
+        .map(|note: &Note| {
+            Line::from(vec![
+                Span::from(format!("{} ", note.created_at.format("%Y-%m-%d %H:%M"))).dim(),
+                Span::from(format!("{}: ", note.author)).bold(),
+                Span::from(note.body.lines().next().unwrap_or_default().to_string()),
+            ])
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(notes).block(Block::bordered().title(" Notes ")),
+        notes_area,
+    );
+}
+
+/// Log for a Task attempt, colored by level
+fn render_logs(
+    frame: &mut Frame,
+    area: Rect,
+    task_id: &str,
+    attempt: &u32,
+    try_number: &u32,
+    lines: &[LogLine],
+    scroll: &usize,
+) {
+    // Only build the lines that fit, starting from where we scrolled to
+    let text: Vec<Line> = lines
+        .iter()
+        .skip(*scroll)
+        .take(usize::from(area.height))
+        .map(|line: &LogLine| {
+            let style: Style = line.level.as_ref().map_or(Style::new(), log_level_style);
+            Line::from(vec![
+                Span::from(format!("{:>5} ", line.line_number)).dim(),
+                Span::styled(line.message.clone(), style),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(
+        Paragraph::new(text).block(
+            Block::bordered().title(format!(" {task_id} attempt {attempt} of {try_number} ")),
+        ),
+        area,
+    );
+}
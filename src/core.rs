@@ -1,16 +1,45 @@
 use crate::{
-    Config,
+    Config, Environment,
     db::{
-        dag_run_select, dag_runs_by_system_select, search_systems_select,
-        system_for_dag_run_select, system_select, task_select, tasks_for_dag_run_select,
+        active_dag_runs_select, active_tasks_select, airflow_tables_missing_select,
+        dag_run_airflow_notes_select, dag_run_calendar_by_system_select,
+        dag_run_durations_by_system_select, dag_run_outcomes_select, dag_run_select,
+        dag_runs_by_system_select, dag_runs_by_systems_select, failure_acknowledge_upsert,
+        failure_assign_upsert, failure_resolve_upsert, failure_select, failure_stale_clear,
+        failures_select, flaky_tasks_by_system_select, kyubey_schema_latest_version,
+        kyubey_schema_version_select, note_insert, notes_select, ping_select, saved_search_delete,
+        saved_search_upsert, saved_searches_select, search_systems_select,
+        sla_breaches_by_system_select, sla_breaches_insert, slas_select, system_for_dag_run_select,
+        system_select, task_airflow_notes_select, task_durations_by_system_select,
+        task_failures_by_system_select, task_select, tasks_for_dag_run_select,
+        usual_dag_run_durations_select, usual_task_durations_select,
     },
+    error::{Entity, KyubeyError},
 };
-use chrono::{DateTime, Utc};
-use poem::error::{InternalServerError, NotFound};
-use poem_openapi::{Enum, Object};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeDelta, Utc};
+use flate2::{Compression, write::GzEncoder};
+use poem_openapi::{Enum, Object, types::ToJSON};
+use regex::Regex;
+use serde::Deserialize;
+use similar::{Algorithm, DiffOp, capture_diff_slices_deadline};
 use sqlx::{Postgres, Transaction};
-use std::{fmt, io::ErrorKind, path::PathBuf, str::FromStr};
-use tokio::fs;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    io::{self, BufWriter, ErrorKind, Read, Write},
+    path::PathBuf,
+    str::FromStr,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+use tokio::{
+    fs,
+    sync::mpsc,
+    task::{JoinSet, spawn_blocking},
+};
+use tokio_stream::wrappers::ReceiverStream;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
 
 /// A single system
 #[derive(Object)]
@@ -23,8 +52,31 @@ pub struct System {
     pub number_of_dag_runs: u64,
 }
 
+/// A System found by searching every environment, tagged with where it lives
+#[derive(Object)]
+pub struct EnvironmentSystem {
+    pub environment: String,
+    #[oai(flatten)]
+    pub system: System,
+}
+
+/// An environment a search couldn't reach, and why
+#[derive(Object)]
+pub struct EnvironmentWarning {
+    pub environment: String,
+    pub message: String,
+}
+
+/// Systems found across every environment, plus the environments that couldn't be searched
+#[derive(Object)]
+pub struct EnvironmentSearch {
+    pub systems: Vec<EnvironmentSystem>,
+    pub warnings: Vec<EnvironmentWarning>,
+    pub has_more: bool,
+}
+
 /// All States a DAG can be in
-#[derive(Enum)]
+#[derive(Clone, Copy, Enum, Eq, PartialEq)]
 #[oai(rename_all = "lowercase")]
 pub enum DagState {
     Failed,
@@ -41,20 +93,40 @@ impl FromStr for DagState {
         match text {
             "failed" => Ok(Self::Failed),
             "queued" => Ok(Self::Queued),
-            "runnning" => Ok(Self::Running),
+            "running" => Ok(Self::Running),
             "success" => Ok(Self::Success),
             _ => Err(()),
         }
     }
 }
 
+impl DagState {
+    /// Will this Dag Run change state again?
+    pub fn is_terminal(&self) -> bool {
+        match self {
+            DagState::Failed | DagState::Success => true,
+            DagState::Queued | DagState::Running => false,
+        }
+    }
+
+    /// How bad is this state, higher is worse
+    pub fn severity(&self) -> u8 {
+        match self {
+            DagState::Success => 0,
+            DagState::Queued => 1,
+            DagState::Running => 2,
+            DagState::Failed => 3,
+        }
+    }
+}
+
 impl fmt::Display for DagState {
     /// How to formate the DagState for HTML rendering
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
         let text: &str = match self {
             DagState::Failed => "failed",
             DagState::Queued => "queued",
-            DagState::Running => "runnning",
+            DagState::Running => "running",
             DagState::Success => "success",
         };
         write!(formatter, "{}", text)
@@ -71,6 +143,93 @@ pub struct DagRun {
     pub state: Option<DagState>,
     pub start_date: Option<DateTime<Utc>>,
     pub end_date: Option<DateTime<Utc>>,
+    pub sla_status: Option<SlaStatus>,
+}
+
+/// A promise of when a System's Dag Runs will be done by, for one Dag or the whole System
+#[derive(Clone, Deserialize, Object)]
+pub struct Sla {
+    pub system_id: String,
+    pub dag_id: Option<String>,
+    pub max_duration_minutes: Option<u32>,
+    pub deadline_minutes: Option<u32>,
+}
+
+impl Sla {
+    /// When a Dag Run must be done by, the tighter of its deadline and max duration
+    fn due(&self, dag_run: &DagRun) -> Option<DateTime<Utc>> {
+        let deadline: Option<DateTime<Utc>> = self
+            .deadline_minutes
+            .map(|minutes: u32| dag_run.execution_date + TimeDelta::minutes(minutes.into()));
+        let max_duration: Option<DateTime<Utc>> = self
+            .max_duration_minutes
+            .zip(dag_run.start_date)
+            .map(|(minutes, start_date)| start_date + TimeDelta::minutes(minutes.into()));
+
+        match (deadline, max_duration) {
+            (Some(deadline), Some(max_duration)) => Some(deadline.min(max_duration)),
+            (deadline, max_duration) => deadline.or(max_duration),
+        }
+    }
+
+    /// Did a Dag Run keep this SLA, as of now. Only a successful run counts as finished
+    fn status(&self, dag_run: &DagRun, now: &DateTime<Utc>) -> SlaStatus {
+        let finished_at: Option<DateTime<Utc>> = match dag_run.state {
+            Some(DagState::Success) => dag_run.end_date,
+            _ => None,
+        };
+
+        match (self.due(dag_run), finished_at) {
+            (Some(due), Some(finished_at)) if finished_at > due => SlaStatus::Breached,
+            (Some(_), Some(_)) => SlaStatus::Met,
+            (Some(due), None) if *now > due => SlaStatus::Breached,
+            _ => SlaStatus::Pending,
+        }
+    }
+}
+
+/// A Dag Run that missed its SLA, as Kyubey records it
+pub struct SlaBreach {
+    pub run_id: String,
+    pub system_id: String,
+    pub dag_id: String,
+    pub due_at: DateTime<Utc>,
+}
+
+/// SLAs as written in the SLA file
+#[derive(Deserialize)]
+pub struct SlaFile {
+    #[serde(default)]
+    pub sla: Vec<Sla>,
+}
+
+/// Did a Dag Run finish within its SLA
+#[derive(Clone, Copy, Enum, Eq, PartialEq)]
+#[oai(rename_all = "lowercase")]
+pub enum SlaStatus {
+    Breached,
+    Met,
+    Pending,
+}
+
+impl fmt::Display for SlaStatus {
+    /// How to formate the SlaStatus for HTML rendering
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: &str = match self {
+            SlaStatus::Breached => "sla breached",
+            SlaStatus::Met => "sla met",
+            SlaStatus::Pending => "sla pending",
+        };
+        write!(formatter, "{}", text)
+    }
+}
+
+/// A System's SLAs and the Dag Runs that broke them
+#[derive(Object)]
+pub struct SystemSlas {
+    pub system: System,
+    pub slas: Vec<Sla>,
+    pub breaches: Vec<DagRun>,
 }
 
 /// All Dag Runs for a System
@@ -78,10 +237,92 @@ pub struct DagRun {
 pub struct SystemDagRuns {
     pub system: System,
     pub dag_runs: Vec<DagRun>,
+    pub notes: Vec<Note>,
+}
+
+/// Where a note was written
+#[derive(Clone, Copy, Enum, Eq, PartialEq)]
+#[oai(rename_all = "lowercase")]
+pub enum NoteSource {
+    Airflow,
+    Kyubey,
+}
+
+/// A markdown note left on a System, Dag Run or Task attempt
+#[derive(Object)]
+pub struct Note {
+    pub source: NoteSource,
+    pub author: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a note is left on, a System, one of its Dag Runs, or an attempt of one of its Tasks
+#[derive(Clone, Deserialize, Object)]
+pub struct NoteTarget {
+    pub system_id: Option<String>,
+    pub run_id: Option<String>,
+    pub task_id: Option<String>,
+    pub attempt: Option<u32>,
+}
+
+/// A note someone wants to leave
+#[derive(Deserialize, Object)]
+pub struct NewNote {
+    pub system_id: Option<String>,
+    pub run_id: Option<String>,
+    pub task_id: Option<String>,
+    pub attempt: Option<u32>,
+    pub author: String,
+    pub body: String,
+}
+
+impl NoteTarget {
+    /// Notes on a System
+    pub fn system(system_id: &str) -> Self {
+        NoteTarget {
+            system_id: Some(system_id.to_string()),
+            run_id: None,
+            task_id: None,
+            attempt: None,
+        }
+    }
+
+    /// Notes on a Dag Run
+    pub fn dag_run(run_id: &str) -> Self {
+        NoteTarget {
+            system_id: None,
+            run_id: Some(run_id.to_string()),
+            task_id: None,
+            attempt: None,
+        }
+    }
+
+    /// Notes on an attempt of a Task
+    pub fn task(run_id: &str, task_id: &str, attempt: &u32) -> Self {
+        NoteTarget {
+            system_id: None,
+            run_id: Some(run_id.to_string()),
+            task_id: Some(task_id.to_string()),
+            attempt: Some(*attempt),
+        }
+    }
+}
+
+impl NewNote {
+    /// What this note is being left on
+    pub fn target(&self) -> NoteTarget {
+        NoteTarget {
+            system_id: self.system_id.clone(),
+            run_id: self.run_id.clone(),
+            task_id: self.task_id.clone(),
+            attempt: self.attempt,
+        }
+    }
 }
 
 /// The states an Airflow Task can be in
-#[derive(Enum)]
+#[derive(Clone, Copy, Enum, Eq, PartialEq)]
 #[oai(rename_all = "lowercase")]
 pub enum TaskState {
     Deferred,
@@ -121,6 +362,26 @@ impl FromStr for TaskState {
     }
 }
 
+impl TaskState {
+    /// Will this Task change state again?
+    pub fn is_terminal(&self) -> bool {
+        match self {
+            TaskState::Failed
+            | TaskState::Removed
+            | TaskState::Skipped
+            | TaskState::Success
+            | TaskState::UpstreamFailed => true,
+            TaskState::Deferred
+            | TaskState::Queued
+            | TaskState::Restarting
+            | TaskState::Running
+            | TaskState::Scheduled
+            | TaskState::UpForReschedule
+            | TaskState::UpForRetry => false,
+        }
+    }
+}
+
 impl fmt::Display for TaskState {
     /// How to formate the DagState for HTML rendering
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -158,137 +419,2720 @@ pub struct Task {
 pub struct DagRunTasks {
     pub dag_run: DagRun,
     pub tasks: Vec<Task>,
+    pub notes: Vec<Note>,
 }
 
-/// How much do we want to paginate by
-const PAGE_SIZE: u32 = 50;
+/// Log levels used by Airflow's Python logger, ordered by severity
+#[derive(Clone, Copy, Deserialize, Enum, Eq, Ord, PartialEq, PartialOrd)]
+#[oai(rename_all = "UPPERCASE")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
 
-/// Pull details for a single system
-pub async fn system_read(
-    tx: &mut Transaction<'_, Postgres>,
-    system_id: &str,
-) -> Result<System, poem::Error> {
-    // Pull details Systems
-    let system: System = match system_select(tx, system_id).await {
-        Ok(system) => Ok(system),
-        Err(sqlx::Error::RowNotFound) => Err(NotFound(sqlx::Error::RowNotFound)),
-        Err(err) => Err(InternalServerError(err)),
-    }?;
+impl FromStr for LogLevel {
+    type Err = ();
 
-    Ok(system)
+    /// Map a string to a Log Level, string come from an Airflow log line
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "DEBUG" => Ok(Self::Debug),
+            "INFO" => Ok(Self::Info),
+            "WARNING" | "WARN" => Ok(Self::Warning),
+            "ERROR" => Ok(Self::Error),
+            "CRITICAL" | "FATAL" => Ok(Self::Critical),
+            _ => Err(()),
+        }
+    }
 }
 
-/// Pull details for System based off Run ID
-pub async fn system_for_dag_run_read(
-    tx: &mut Transaction<'_, Postgres>,
-    run_id: &str,
-) -> Result<System, poem::Error> {
-    // Pull details for a dag run
-    let system: System = match system_for_dag_run_select(tx, run_id).await {
-        Ok(system) => Ok(system),
-        Err(sqlx::Error::RowNotFound) => Err(NotFound(sqlx::Error::RowNotFound)),
-        Err(err) => Err(InternalServerError(err)),
-    }?;
+impl fmt::Display for LogLevel {
+    /// How to formate the LogLevel for HTML rendering
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: &str = match self {
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warning => "WARNING",
+            LogLevel::Error => "ERROR",
+            LogLevel::Critical => "CRITICAL",
+        };
+        write!(formatter, "{}", text)
+    }
+}
 
-    Ok(system)
+/// A single line of a task log, split into Airflow's log format when possible.
+/// The message has ANSI escape codes removed, the text is the line as written.
+#[derive(Object)]
+pub struct LogLine {
+    pub line_number: u64,
+    pub timestamp: Option<String>,
+    pub source: Option<String>,
+    pub level: Option<LogLevel>,
+    pub message: String,
+    pub text: String,
 }
 
-/// Search for a System
-pub async fn search_systems_read(
-    tx: &mut Transaction<'_, Postgres>,
-    search_by: &str,
-    page: &u32,
-) -> Result<Vec<System>, poem::Error> {
-    // Compute offset
-    let offset: u32 = page * PAGE_SIZE;
+/// The 16 colors ANSI escape codes can select
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum AnsiColor {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
 
-    // Pull the Systems
-    search_systems_select(tx, search_by, PAGE_SIZE, offset)
-        .await
-        .map_err(InternalServerError)
+impl AnsiColor {
+    /// Map a 0 to 15 color index to a color
+    fn from_index(index: u16) -> Option<Self> {
+        let color: Self = match index {
+            0 => Self::Black,
+            1 => Self::Red,
+            2 => Self::Green,
+            3 => Self::Yellow,
+            4 => Self::Blue,
+            5 => Self::Magenta,
+            6 => Self::Cyan,
+            7 => Self::White,
+            8 => Self::BrightBlack,
+            9 => Self::BrightRed,
+            10 => Self::BrightGreen,
+            11 => Self::BrightYellow,
+            12 => Self::BrightBlue,
+            13 => Self::BrightMagenta,
+            14 => Self::BrightCyan,
+            15 => Self::BrightWhite,
+            _ => return None,
+        };
+        Some(color)
+    }
 }
 
-/// Pull details for a dag run
-pub async fn dag_run_read(
-    tx: &mut Transaction<'_, Postgres>,
-    run_id: &str,
-) -> Result<DagRun, poem::Error> {
-    // Pull details for a dag run
-    let dag_run: DagRun = match dag_run_select(tx, run_id).await {
-        Ok(dag_run) => Ok(dag_run),
-        Err(sqlx::Error::RowNotFound) => Err(NotFound(sqlx::Error::RowNotFound)),
-        Err(err) => Err(InternalServerError(err)),
-    }?;
+/// Text styling set by ANSI SGR escape codes
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct AnsiStyle {
+    pub foreground: Option<AnsiColor>,
+    pub background: Option<AnsiColor>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
 
-    Ok(dag_run)
+impl AnsiStyle {
+    /// Apply the parameters of a single SGR sequence (`ESC [ ... m`)
+    fn apply_sgr(&mut self, params: &str) {
+        let mut codes = params
+            .split([';', ':'])
+            .map(|code: &str| code.parse::<u16>().unwrap_or(0));
+
+        // An empty parameter list is a reset
+        if params.is_empty() {
+            *self = Self::default();
+        }
+
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Self::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                30..=37 => self.foreground = AnsiColor::from_index(code - 30),
+                39 => self.foreground = None,
+                40..=47 => self.background = AnsiColor::from_index(code - 40),
+                49 => self.background = None,
+                90..=97 => self.foreground = AnsiColor::from_index(code - 90 + 8),
+                100..=107 => self.background = AnsiColor::from_index(code - 100 + 8),
+                // Extended colors: 5;n for the 256 color palette, 2;r;g;b for true color
+                38 | 48 => {
+                    let color: Option<AnsiColor> = match codes.next() {
+                        Some(5) => codes.next().and_then(AnsiColor::from_index),
+                        Some(2) => {
+                            codes.nth(2);
+                            None
+                        }
+                        _ => None,
+                    };
+                    match code {
+                        38 => self.foreground = color,
+                        _ => self.background = color,
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
 }
 
-/// Dag Runs by System
-pub async fn dag_runs_for_system_read(
-    tx: &mut Transaction<'_, Postgres>,
-    system_id: &str,
-) -> Result<SystemDagRuns, poem::Error> {
-    // Pull the Systems
-    let system: System = system_read(tx, system_id).await?;
+/// A run of log text sharing the same ANSI styling
+pub struct AnsiSpan {
+    pub style: AnsiStyle,
+    pub text: String,
+}
 
-    // Pull dag runs for that system
-    let dag_runs: Vec<DagRun> = dag_runs_by_system_select(tx, system_id)
-        .await
-        .map_err(InternalServerError)?;
+/// Split a line of text on its ANSI escape codes, dropping any code that is not styling
+pub fn ansi_spans(text: &str) -> Vec<AnsiSpan> {
+    let mut spans: Vec<AnsiSpan> = Vec::new();
+    let mut style: AnsiStyle = AnsiStyle::default();
+    let mut current: String = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        // Keep plain text
+        if char != '\u{1b}' {
+            current.push(char);
+            continue;
+        }
 
-    Ok(SystemDagRuns { system, dag_runs })
+        match chars.next() {
+            // Control Sequence: parameters until a final byte in @ to ~
+            Some('[') => {
+                let mut params: String = String::new();
+                let mut last: Option<char> = None;
+                for char in chars.by_ref() {
+                    if ('@'..='~').contains(&char) {
+                        last = Some(char);
+                        break;
+                    }
+                    params.push(char);
+                }
+
+                // Only SGR sequences change the style
+                if last == Some('m') {
+                    let mut next_style: AnsiStyle = style;
+                    next_style.apply_sgr(&params);
+                    if next_style != style {
+                        if !current.is_empty() {
+                            spans.push(AnsiSpan {
+                                style,
+                                text: std::mem::take(&mut current),
+                            });
+                        }
+                        style = next_style;
+                    }
+                }
+            }
+            // Operating System Command: skip until BEL or String Terminator
+            Some(']') => {
+                while let Some(char) = chars.next() {
+                    if char == '\u{7}' {
+                        break;
+                    }
+                    if char == '\u{1b}' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Any other escape is a single character we can drop
+            _ => {}
+        }
+    }
+
+    if !current.is_empty() {
+        spans.push(AnsiSpan {
+            style,
+            text: current,
+        });
+    }
+
+    spans
 }
 
-/// Tasks by Run ID
-pub async fn tasks_for_dag_run_read(
-    tx: &mut Transaction<'_, Postgres>,
-    run_id: &str,
-) -> Result<DagRunTasks, poem::Error> {
-    // Pull the DAG Run
-    let dag_run: DagRun = dag_run_read(tx, run_id).await?;
+/// Remove all ANSI escape codes from some text
+pub fn strip_ansi(text: &str) -> String {
+    ansi_spans(text)
+        .into_iter()
+        .map(|span: AnsiSpan| span.text)
+        .collect()
+}
 
-    // Pull all Tasks for a Dag Run
-    let tasks: Vec<Task> = tasks_for_dag_run_select(tx, run_id)
-        .await
-        .map_err(InternalServerError)?;
+/// How a line changed between two attempts of a task
+#[derive(Enum, Eq, PartialEq)]
+#[oai(rename_all = "lowercase")]
+pub enum LogChange {
+    Equal,
+    Removed,
+    Added,
+    Changed,
+}
 
-    Ok(DagRunTasks { dag_run, tasks })
+/// One side of a row in a log diff
+#[derive(Object)]
+pub struct LogDiffLine {
+    pub line_number: u64,
+    pub text: String,
 }
 
-/// Pull details for a task
-pub async fn task_read(
-    tx: &mut Transaction<'_, Postgres>,
-    run_id: &str,
-    task_id: &str,
-) -> Result<Task, poem::Error> {
-    // Pull details for a dag run
-    let task: Task = match task_select(tx, run_id, task_id).await {
-        Ok(task) => Ok(task),
-        Err(sqlx::Error::RowNotFound) => Err(NotFound(sqlx::Error::RowNotFound)),
-        Err(err) => Err(InternalServerError(err)),
-    }?;
+/// A row of a side by side log diff. Left is the first attempt, right is the second.
+#[derive(Object)]
+pub struct LogDiffRow {
+    pub change: LogChange,
+    pub left: Option<LogDiffLine>,
+    pub right: Option<LogDiffLine>,
+}
 
-    Ok(task)
+/// Side by side diff between two attempts of a task
+#[derive(Object)]
+pub struct LogDiff {
+    pub left_attempt: u32,
+    pub right_attempt: u32,
+    pub rows: Vec<LogDiffRow>,
 }
 
-/// Return the content of a log
-pub async fn log_read(
-    config: &Config,
-    dag_id: &str,
-    run_id: &str,
-    task_id: &str,
-    attepmt: &u32,
-) -> Result<String, poem::Error> {
-    // The path to our log
-    let log_path = PathBuf::from(format!(
-        "{}/dag_id={}/run_id={}/task_id={}/attempt={}.log",
-        config.log_path, dag_id, run_id, task_id, attepmt,
-    ));
+/// Archive formats a bundle of logs can be downloaded as
+#[derive(Clone, Copy)]
+pub enum LogBundleFormat {
+    Zip,
+    TarGz,
+}
 
-    // Read Log as String from file, and do it async
-    match fs::read_to_string(log_path).await {
-        Ok(log) => Ok(log),
-        Err(err) if err.kind() == ErrorKind::NotFound => Err(NotFound(err)),
-        Err(err) => Err(InternalServerError(err)),
+/// A log file that belongs in a log bundle
+#[derive(Object)]
+pub struct LogBundleFile {
+    pub task_id: String,
+    pub attempt: u32,
+    pub path: String,
+    pub found: bool,
+}
+
+/// Manifest describing everything in a log bundle
+#[derive(Object)]
+pub struct LogBundleManifest {
+    pub dag_run: DagRun,
+    pub tasks: Vec<Task>,
+    pub files: Vec<LogBundleFile>,
+}
+
+/// How long a finished Dag Run or Task took, in seconds
+pub struct RunDuration {
+    pub dag_id: String,
+    pub task_id: Option<String>,
+    pub run_id: String,
+    pub execution_date: DateTime<Utc>,
+    pub duration: f64,
+}
+
+/// Summary of how long something usually takes, in seconds
+#[derive(Object)]
+pub struct DurationStats {
+    pub runs: u64,
+    pub median: f64,
+    pub p90: f64,
+    pub p99: f64,
+}
+
+/// Median duration of everything that ran on a day, in seconds
+#[derive(Object)]
+pub struct DurationPoint {
+    pub date: NaiveDate,
+    pub median: f64,
+}
+
+/// A run that took far longer than usual
+#[derive(Object)]
+pub struct DurationOutlier {
+    pub run_id: String,
+    pub execution_date: DateTime<Utc>,
+    pub duration: f64,
+}
+
+/// Duration statistics for a Dag
+#[derive(Object)]
+pub struct DagDurations {
+    pub dag_id: String,
+    pub stats: DurationStats,
+    pub trend: Vec<DurationPoint>,
+    pub outliers: Vec<DurationOutlier>,
+}
+
+/// Duration statistics for a Task
+#[derive(Object)]
+pub struct TaskDurations {
+    pub dag_id: String,
+    pub task_id: String,
+    pub stats: DurationStats,
+    pub trend: Vec<DurationPoint>,
+    pub outliers: Vec<DurationOutlier>,
+}
+
+/// Duration statistics for everything a System runs
+#[derive(Object)]
+pub struct SystemDurations {
+    pub system: System,
+    pub trend: Vec<DurationPoint>,
+    pub dags: Vec<DagDurations>,
+    pub tasks: Vec<TaskDurations>,
+}
+
+/// A Dag Run or Task still in flight, and which System it belongs to
+pub struct ActiveRun {
+    pub client_name: String,
+    pub client_id: String,
+    pub system_name: String,
+    pub system_id: String,
+    pub dag_id: String,
+    pub run_id: String,
+    pub task_id: Option<String>,
+    pub state: String,
+    pub queued_at: Option<DateTime<Utc>>,
+    pub start_date: Option<DateTime<Utc>>,
+}
+
+/// How long a Dag or Task usually takes, in seconds
+pub struct UsualDuration {
+    pub dag_id: String,
+    pub task_id: Option<String>,
+    pub median: f64,
+}
+
+/// Why a Dag Run or Task needs attention
+#[derive(Clone, Copy, Enum, Eq, PartialEq)]
+#[oai(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    StuckQueued,
+    LongRunningDagRun,
+    LongRunningTask,
+}
+
+impl fmt::Display for AnomalyKind {
+    /// How to formate the AnomalyKind for HTML rendering
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: &str = match self {
+            AnomalyKind::StuckQueued => "Stuck in Queue",
+            AnomalyKind::LongRunningDagRun => "Long Running Dag Run",
+            AnomalyKind::LongRunningTask => "Long Running Task",
+        };
+        write!(formatter, "{}", text)
+    }
+}
+
+/// A Dag Run or Task that has been waiting or running far longer than it should
+#[derive(Object)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub client_name: String,
+    pub client_id: String,
+    pub system_name: String,
+    pub system_id: String,
+    pub dag_id: String,
+    pub run_id: String,
+    pub task_id: Option<String>,
+    pub since: DateTime<Utc>,
+    pub elapsed: f64,
+    pub usual: Option<f64>,
+}
+
+impl Anomaly {
+    /// What makes this anomaly unique, so we only alert on it once
+    pub fn key(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            self.kind,
+            self.run_id,
+            self.task_id.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// Where a failed Dag Run is in being looked after
+#[derive(Clone, Copy, Default, Deserialize, Enum, Eq, PartialEq)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FailureStatus {
+    #[default]
+    Unacknowledged,
+    Acknowledged,
+    Resolved,
+}
+
+impl fmt::Display for FailureStatus {
+    /// How to formate the FailureStatus for HTML rendering
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: &str = match self {
+            FailureStatus::Unacknowledged => "unacknowledged",
+            FailureStatus::Acknowledged => "acknowledged",
+            FailureStatus::Resolved => "resolved",
+        };
+        write!(formatter, "{}", text)
+    }
+}
+
+/// A failed Dag Run, and who is looking after it
+#[derive(Object)]
+pub struct Failure {
+    pub client_name: String,
+    pub client_id: String,
+    pub system_name: String,
+    pub system_id: String,
+    pub dag_id: String,
+    pub run_id: String,
+    pub failed_at: Option<DateTime<Utc>>,
+    pub status: FailureStatus,
+    pub assignee: Option<String>,
+    pub acknowledged_by: Option<String>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// What someone is doing about a failed Dag Run
+#[derive(Clone, Copy, Deserialize, Enum, Eq, PartialEq)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FailureAction {
+    Acknowledge,
+    Assign,
+    Resolve,
+}
+
+impl fmt::Display for FailureAction {
+    /// How to formate the FailureAction for HTML rendering
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: &str = match self {
+            FailureAction::Acknowledge => "acknowledge",
+            FailureAction::Assign => "assign",
+            FailureAction::Resolve => "resolve",
+        };
+        write!(formatter, "{}", text)
+    }
+}
+
+/// Who is acting on a failed Dag Run, and who it goes to when assigning
+#[derive(Deserialize, Object)]
+pub struct FailureUpdate {
+    pub by: String,
+    pub assignee: Option<String>,
+}
+
+/// A search someone wants to run again, kept under a name
+#[derive(Object)]
+pub struct SavedSearch {
+    pub name: String,
+    pub search_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A search someone wants to save
+#[derive(Deserialize, Object)]
+pub struct NewSavedSearch {
+    pub name: String,
+    pub search_by: String,
+}
+
+/// How a Dag Run turned out, and which System it belongs to
+pub struct RunOutcome {
+    pub client_name: String,
+    pub client_id: String,
+    pub system_name: String,
+    pub system_id: String,
+    pub dag_id: String,
+    pub execution_date: DateTime<Utc>,
+    pub end_date: Option<DateTime<Utc>>,
+    pub state: Option<DagState>,
+    pub tasks: u64,
+    pub retried_tasks: u64,
+}
+
+/// How far back a scorecard looks
+#[derive(Clone, Copy, Default, Deserialize, Enum, Eq, PartialEq)]
+#[oai(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ScorecardWindow {
+    Day,
+    #[default]
+    Week,
+    Month,
+    Quarter,
+}
+
+impl ScorecardWindow {
+    /// How many days the window covers
+    pub fn days(&self) -> i64 {
+        match self {
+            ScorecardWindow::Day => 1,
+            ScorecardWindow::Week => 7,
+            ScorecardWindow::Month => 30,
+            ScorecardWindow::Quarter => 90,
+        }
+    }
+}
+
+impl fmt::Display for ScorecardWindow {
+    /// How to formate the ScorecardWindow for HTML rendering
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: &str = match self {
+            ScorecardWindow::Day => "day",
+            ScorecardWindow::Week => "week",
+            ScorecardWindow::Month => "month",
+            ScorecardWindow::Quarter => "quarter",
+        };
+        write!(formatter, "{}", text)
+    }
+}
+
+/// How reliable a System or Dag has been. Rates are 0 to 1, recovery time is in seconds.
+/// A rate is None until there is something to rate, like a finished Dag Run.
+#[derive(Object)]
+pub struct Reliability {
+    pub runs: u64,
+    pub successes: u64,
+    pub failures: u64,
+    pub success_rate: Option<f64>,
+    pub failure_rate: Option<f64>,
+    pub mean_time_to_recovery: Option<f64>,
+    pub task_retry_rate: Option<f64>,
+}
+
+/// Reliability of a single Dag
+#[derive(Object)]
+pub struct DagScorecard {
+    pub dag_id: String,
+    pub reliability: Reliability,
+}
+
+/// Reliability of a System, and each of its Dags
+#[derive(Object)]
+pub struct SystemScorecard {
+    pub client_name: String,
+    pub client_id: String,
+    pub system_name: String,
+    pub system_id: String,
+    pub reliability: Reliability,
+    pub dags: Vec<DagScorecard>,
+}
+
+/// How many Dag Runs a System had on a day, and the worst state they ended up in
+#[derive(Object)]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub runs: u64,
+    pub worst_state: Option<DagState>,
+}
+
+/// How often a Task needed retries before it succeeded or gave up
+#[derive(Object)]
+pub struct FlakyTask {
+    pub dag_id: String,
+    pub task_id: String,
+    pub runs: u64,
+    pub retried: u64,
+    pub flaky_successes: u64,
+    pub exhausted: u64,
+    pub failed_attempts: u64,
+    pub mean_tries: f64,
+    pub retry_rate: f64,
+}
+
+/// A System's Tasks that needed retries, flakiest first
+#[derive(Object)]
+pub struct SystemFlakyTasks {
+    pub system: System,
+    pub tasks: Vec<FlakyTask>,
+}
+
+/// How one thing an environment depends on is doing
+#[derive(Object)]
+pub struct Check {
+    pub ok: bool,
+    #[oai(skip_serializing_if_is_none)]
+    pub latency_ms: Option<f64>,
+    pub detail: String,
+}
+
+/// Can an environment serve requests
+#[derive(Object)]
+pub struct EnvironmentReadiness {
+    pub environment: String,
+    pub ready: bool,
+    pub database: Check,
+    pub logs: Check,
+    pub schema: Check,
+}
+
+/// Can Kyubey serve requests, as long as any environment can, with each environment's own health
+#[derive(Object)]
+pub struct Readiness {
+    pub ready: bool,
+    pub environments: Vec<EnvironmentReadiness>,
+}
+
+/// How many weeks the Dag Run calendar covers
+pub const CALENDAR_WEEKS: i64 = 52;
+
+/// How much we paginate by, unless configured otherwise
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
+/// Pull details for a single system
+pub async fn system_read(
+    tx: &mut Transaction<'_, Postgres>,
+    system_id: &str,
+) -> Result<System, KyubeyError> {
+    // Pull details Systems
+    let system: System = match system_select(tx, system_id).await {
+        Ok(system) => Ok(system),
+        Err(sqlx::Error::RowNotFound) => Err(KyubeyError::not_found(Entity::System, system_id)),
+        Err(err) => Err(KyubeyError::Backend(err)),
+    }?;
+
+    Ok(system)
+}
+
+/// Pull details for System based off Run ID
+pub async fn system_for_dag_run_read(
+    tx: &mut Transaction<'_, Postgres>,
+    run_id: &str,
+) -> Result<System, KyubeyError> {
+    // Pull details for a dag run
+    let system: System = match system_for_dag_run_select(tx, run_id).await {
+        Ok(system) => Ok(system),
+        Err(sqlx::Error::RowNotFound) => Err(KyubeyError::not_found(Entity::DagRun, run_id)),
+        Err(err) => Err(KyubeyError::Backend(err)),
+    }?;
+
+    Ok(system)
+}
+
+/// Search for a System
+pub async fn search_systems_read(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    search_by: &str,
+    page: &u32,
+) -> Result<Vec<System>, KyubeyError> {
+    // Compute offset
+    let offset: u32 = page * config.page_size;
+
+    // Pull the Systems
+    search_systems_select(tx, search_by, config.page_size, offset)
+        .await
+        .map_err(KyubeyError::Backend)
+}
+
+/// Search for a System in every environment at once, merging the results newest first
+///
+/// An environment that can't be reached is reported as a warning instead of failing the search.
+pub async fn search_environments_read(
+    environments: &[Environment],
+    search_by: &str,
+    page: &u32,
+) -> EnvironmentSearch {
+    // Every environment pages by the same amount
+    let page_size: u32 = environments
+        .first()
+        .map(|environment: &Environment| environment.config.page_size)
+        .unwrap_or(DEFAULT_PAGE_SIZE);
+
+    // We can't know how environments interleave, so each covers this page and every page before it,
+    // plus one more row to tell if there is a next page
+    let limit: u32 = page
+        .saturating_add(1)
+        .saturating_mul(page_size)
+        .saturating_add(1);
+
+    // Ask every environment at once
+    let mut searches: JoinSet<(String, Result<Vec<System>, sqlx::Error>)> = JoinSet::new();
+    for environment in environments {
+        let environment: Environment = environment.clone();
+        let search_by: String = search_by.to_string();
+        searches.spawn(async move {
+            let found: Result<Vec<System>, sqlx::Error> = async {
+                let mut tx: Transaction<'_, Postgres> = environment.pool.begin().await?;
+                search_systems_select(&mut tx, &search_by, limit, 0).await
+            }
+            .await;
+
+            (environment.config.environment, found)
+        });
+    }
+
+    let mut systems: Vec<EnvironmentSystem> = Vec::new();
+    let mut warnings: Vec<EnvironmentWarning> = Vec::new();
+    while let Some(search) = searches.join_next().await {
+        let (environment, found) =
+            search.unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()));
+        match found {
+            Ok(found) => {
+                systems.extend(found.into_iter().map(|system: System| EnvironmentSystem {
+                    environment: environment.clone(),
+                    system,
+                }))
+            }
+            // Connection details stay in our logs, the page only says which environment is out
+            Err(err) => {
+                tracing::error!("Unable to search {environment}: {err}");
+                warnings.push(EnvironmentWarning {
+                    environment,
+                    message: "environment unreachable".to_string(),
+                });
+            }
+        }
+    }
+
+    // Same order a single environment uses, so paging stays stable
+    systems.sort_by(|a: &EnvironmentSystem, b: &EnvironmentSystem| {
+        b.system
+            .latest_run
+            .cmp(&a.system.latest_run)
+            .then_with(|| a.system.system_id.cmp(&b.system.system_id))
+            .then_with(|| a.environment.cmp(&b.environment))
+    });
+
+    // Report unreachable environments in a stable order
+    warnings.sort_by(|a: &EnvironmentWarning, b: &EnvironmentWarning| {
+        a.environment.cmp(&b.environment)
+    });
+
+    let mut systems: Vec<EnvironmentSystem> = systems
+        .into_iter()
+        .skip(page.saturating_mul(page_size) as usize)
+        .take(page_size as usize + 1)
+        .collect();
+    let has_more: bool = systems.len() > page_size as usize;
+    systems.truncate(page_size as usize);
+
+    EnvironmentSearch {
+        systems,
+        warnings,
+        has_more,
+    }
+}
+
+/// Pull details for a dag run
+pub async fn dag_run_read(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    run_id: &str,
+) -> Result<DagRun, KyubeyError> {
+    // Pull details for a dag run
+    let mut dag_run: DagRun = match dag_run_select(tx, run_id).await {
+        Ok(dag_run) => Ok(dag_run),
+        Err(sqlx::Error::RowNotFound) => Err(KyubeyError::not_found(Entity::DagRun, run_id)),
+        Err(err) => Err(KyubeyError::Backend(err)),
+    }?;
+
+    // Check it against its SLA
+    let slas: Vec<Sla> = slas_read(tx, config).await?;
+    dag_run.sla_status = sla_status(&slas, &dag_run, &Utc::now());
+
+    Ok(dag_run)
+}
+
+/// Dag Runs by System
+pub async fn dag_runs_for_system_read(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    system_id: &str,
+) -> Result<SystemDagRuns, KyubeyError> {
+    // Pull the Systems
+    let system: System = system_read(tx, system_id).await?;
+
+    // Pull dag runs for that system
+    let mut dag_runs: Vec<DagRun> = dag_runs_by_system_select(tx, system_id)
+        .await
+        .map_err(KyubeyError::Backend)?;
+
+    // Check them against their SLAs
+    let slas: Vec<Sla> = slas_read(tx, config).await?;
+    let now: DateTime<Utc> = Utc::now();
+    for dag_run in &mut dag_runs {
+        dag_run.sla_status = sla_status(&slas, dag_run, &now);
+    }
+
+    // Notes left on the System
+    let notes: Vec<Note> = notes_read(tx, config, &NoteTarget::system(system_id)).await?;
+
+    Ok(SystemDagRuns {
+        system,
+        dag_runs,
+        notes,
+    })
+}
+
+/// Every SLA Dags are held to, from Kyubey's own schema when we keep one, otherwise the SLA file
+pub async fn slas_read(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+) -> Result<Vec<Sla>, KyubeyError> {
+    match config.kyubey_schema {
+        true => slas_select(tx).await.map_err(KyubeyError::Backend),
+        false => Ok(config.slas.clone()),
+    }
+}
+
+/// Record every recent Dag Run that breached its SLA, returning how many were new
+pub async fn sla_breaches_record(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+) -> Result<u64, KyubeyError> {
+    // Breaches are kept in Kyubey's own schema
+    require_kyubey_schema(config, "Recording SLA breaches")?;
+
+    // Only Systems with an SLA can breach one
+    let slas: Vec<Sla> = slas_read(tx, config).await?;
+    let mut system_ids: Vec<String> = slas.iter().map(|sla: &Sla| sla.system_id.clone()).collect();
+    system_ids.sort();
+    system_ids.dedup();
+    if system_ids.is_empty() {
+        return Ok(0);
+    }
+
+    // Check every recent Dag Run of those Systems
+    let now: DateTime<Utc> = Utc::now();
+    let since: DateTime<Utc> = now - TimeDelta::days(SLA_BREACH_DAYS);
+    let dag_runs: Vec<DagRun> = dag_runs_by_systems_select(tx, &system_ids, &since)
+        .await
+        .map_err(KyubeyError::Backend)?;
+    let breaches: Vec<SlaBreach> = dag_runs
+        .into_iter()
+        .filter_map(|dag_run: DagRun| {
+            let sla: &Sla = sla_for(&slas, &dag_run)?;
+            match sla.status(&dag_run, &now) {
+                SlaStatus::Breached => Some(SlaBreach {
+                    due_at: sla.due(&dag_run)?,
+                    system_id: dag_run.system_id?,
+                    run_id: dag_run.run_id,
+                    dag_id: dag_run.dag_id,
+                }),
+                _ => None,
+            }
+        })
+        .collect();
+    if breaches.is_empty() {
+        return Ok(0);
+    }
+
+    sla_breaches_insert(tx, &breaches)
+        .await
+        .map_err(KyubeyError::Backend)
+}
+
+/// The SLA a Dag Run is held to, a Dag's own SLA wins over its System's
+fn sla_for<'a>(slas: &'a [Sla], dag_run: &DagRun) -> Option<&'a Sla> {
+    let system_id: &str = dag_run.system_id.as_deref()?;
+    let for_system = slas.iter().filter(|sla: &&Sla| sla.system_id == system_id);
+
+    for_system
+        .clone()
+        .find(|sla: &&Sla| sla.dag_id.as_deref() == Some(dag_run.dag_id.as_str()))
+        .or_else(|| for_system.clone().find(|sla: &&Sla| sla.dag_id.is_none()))
+}
+
+/// How a Dag Run stands against its SLA, None if it does not have one
+pub fn sla_status(slas: &[Sla], dag_run: &DagRun, now: &DateTime<Utc>) -> Option<SlaStatus> {
+    sla_for(slas, dag_run).map(|sla: &Sla| sla.status(dag_run, now))
+}
+
+/// A System's SLAs, and every Dag Run that breached them
+pub async fn system_slas_read(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    system_id: &str,
+) -> Result<SystemSlas, KyubeyError> {
+    // Check every dag run for the system
+    let SystemDagRuns {
+        system, dag_runs, ..
+    } = dag_runs_for_system_read(tx, config, system_id).await?;
+
+    let slas: Vec<Sla> = slas_read(tx, config)
+        .await?
+        .into_iter()
+        .filter(|sla: &Sla| sla.system_id == system_id)
+        .collect();
+    let mut breaches: Vec<DagRun> = dag_runs
+        .into_iter()
+        .filter(|dag_run: &DagRun| dag_run.sla_status == Some(SlaStatus::Breached))
+        .collect();
+
+    // Recorded breaches stand even if the SLA has changed since
+    if config.kyubey_schema {
+        let recorded: Vec<DagRun> = sla_breaches_by_system_select(tx, system_id)
+            .await
+            .map_err(KyubeyError::Backend)?;
+        let breached: HashSet<String> = breaches
+            .iter()
+            .map(|dag_run: &DagRun| dag_run.run_id.clone())
+            .collect();
+        breaches.extend(
+            recorded
+                .into_iter()
+                .filter(|dag_run: &DagRun| !breached.contains(&dag_run.run_id))
+                .map(|dag_run: DagRun| DagRun {
+                    sla_status: Some(SlaStatus::Breached),
+                    ..dag_run
+                }),
+        );
+    }
+    breaches.sort_by(|a: &DagRun, b: &DagRun| {
+        b.execution_date
+            .cmp(&a.execution_date)
+            .then_with(|| a.dag_id.cmp(&b.dag_id))
+    });
+
+    Ok(SystemSlas {
+        system,
+        slas,
+        breaches,
+    })
+}
+
+/// Tasks by Run ID
+pub async fn tasks_for_dag_run_read(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    run_id: &str,
+) -> Result<DagRunTasks, KyubeyError> {
+    // Pull the DAG Run
+    let dag_run: DagRun = dag_run_read(tx, config, run_id).await?;
+
+    // Pull all Tasks for a Dag Run
+    let tasks: Vec<Task> = tasks_for_dag_run_select(tx, run_id)
+        .await
+        .map_err(KyubeyError::Backend)?;
+
+    // Notes left on the Dag Run
+    let notes: Vec<Note> = notes_read(tx, config, &NoteTarget::dag_run(run_id)).await?;
+
+    Ok(DagRunTasks {
+        dag_run,
+        tasks,
+        notes,
+    })
+}
+
+/// Pull details for a task
+pub async fn task_read(
+    tx: &mut Transaction<'_, Postgres>,
+    run_id: &str,
+    task_id: &str,
+) -> Result<Task, KyubeyError> {
+    // Pull details for a dag run
+    let task: Task = match task_select(tx, run_id, task_id).await {
+        Ok(task) => Ok(task),
+        Err(sqlx::Error::RowNotFound) => Err(KyubeyError::not_found(
+            Entity::Task,
+            &format!("{}/{}", run_id, task_id),
+        )),
+        Err(err) => Err(KyubeyError::Backend(err)),
+    }?;
+
+    Ok(task)
+}
+
+/// Where Airflow writes the log for a task attempt
+fn log_path(config: &Config, dag_id: &str, run_id: &str, task_id: &str, attepmt: &u32) -> PathBuf {
+    PathBuf::from(format!(
+        "{}/dag_id={}/run_id={}/task_id={}/attempt={}.log",
+        config.log_path, dag_id, run_id, task_id, attepmt,
+    ))
+}
+
+/// Return the content of a log
+pub async fn log_read(
+    config: &Config,
+    dag_id: &str,
+    run_id: &str,
+    task_id: &str,
+    attepmt: &u32,
+) -> Result<String, KyubeyError> {
+    // The path to our log
+    let log_path: PathBuf = log_path(config, dag_id, run_id, task_id, attepmt);
+
+    // Read Log as String from file, and do it async
+    fs::read_to_string(log_path)
+        .await
+        .map_err(|source: io::Error| KyubeyError::LogUnavailable {
+            id: format!("{}/{}/attempt={}", run_id, task_id, attepmt),
+            source,
+        })
+}
+
+/// Split a raw log into lines, parsing `[timestamp] {file.py:line} LEVEL - message` where possible
+pub fn parse_log(log: &str) -> Vec<LogLine> {
+    // Normalized all new lines to expected new lines
+    let log: String = log.replace("\r\n", "\n").replace('\r', "\n");
+
+    // Lines without a header (tracebacks, multi-line messages) belong to the line above them
+    let mut current_level: Option<LogLevel> = None;
+
+    log.lines()
+        .enumerate()
+        .map(|(index, text)| {
+            let line_number: u64 = u64::try_from(index + 1).unwrap_or(u64::MAX);
+
+            // Colored output should not get in the way of parsing
+            let plain: String = strip_ansi(text);
+
+            match parse_log_header(&plain) {
+                Some((timestamp, source, level, message)) => {
+                    current_level = level;
+                    LogLine {
+                        line_number,
+                        timestamp: Some(timestamp.to_string()),
+                        source: Some(source.to_string()),
+                        level,
+                        message: message.to_string(),
+                        text: text.to_string(),
+                    }
+                }
+                None => LogLine {
+                    line_number,
+                    timestamp: None,
+                    source: None,
+                    level: current_level,
+                    message: plain,
+                    text: text.to_string(),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Break an Airflow log line into its timestamp, source, level and message
+fn parse_log_header(text: &str) -> Option<(&str, &str, Option<LogLevel>, &str)> {
+    // [timestamp]
+    let rest: &str = text.strip_prefix('[')?;
+    let (timestamp, rest) = rest.split_once("] ")?;
+
+    // {file.py:line}
+    let rest: &str = rest.strip_prefix('{')?;
+    let (source, rest) = rest.split_once("} ")?;
+
+    // LEVEL - message
+    let (level, message) = match rest.split_once(" - ") {
+        Some((level, message)) => (level, message),
+        None => (rest.strip_suffix(" -")?, ""),
+    };
+
+    Some((timestamp, source, LogLevel::from_str(level).ok(), message))
+}
+
+/// Return the parsed lines of a log, keeping only lines at or above a log level
+pub async fn log_lines_read(
+    config: &Config,
+    dag_id: &str,
+    run_id: &str,
+    task_id: &str,
+    attepmt: &u32,
+    min_level: &Option<LogLevel>,
+) -> Result<Vec<LogLine>, KyubeyError> {
+    // Log for a task attempt
+    let log: String = log_read(config, dag_id, run_id, task_id, attepmt).await?;
+
+    // Parse and filter the log
+    let lines: Vec<LogLine> = parse_log(&log)
+        .into_iter()
+        .filter(|line: &LogLine| match (min_level, &line.level) {
+            (None, _) => true,
+            (Some(min_level), Some(level)) => level >= min_level,
+            (Some(_), None) => false,
+        })
+        .collect();
+
+    Ok(lines)
+}
+
+/// Timestamps as written by Airflow and most Python loggers
+static TIMESTAMP_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?")
+        .expect("valid timestamp regex")
+});
+
+/// Process IDs, such as `pid=1234`, `PID: 1234` or `Started process 1234`
+static PID_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(pid[=:]?\s*|process\s+)\d+").expect("valid pid regex"));
+
+/// How long we are willing to spend aligning two logs before falling back to a rough diff
+const LOG_DIFF_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Remove the parts of a log line that change on every attempt
+fn normalize_log_line(text: &str) -> String {
+    let text: String = strip_ansi(text);
+    let text = TIMESTAMP_REGEX.replace_all(&text, "<timestamp>");
+    PID_REGEX.replace_all(&text, "${1}<pid>").into_owned()
+}
+
+/// Make sure an attempt is one the task has actually made
+fn check_attempt(attempt: &u32, try_number: &u32) -> Result<(), KyubeyError> {
+    if *attempt == 0 || attempt > try_number {
+        return Err(KyubeyError::InvalidInput(format!(
+            "Attempt {} does not exist, the task has {} attempts",
+            attempt, try_number
+        )));
+    }
+
+    Ok(())
+}
+
+/// Compare the logs of two attempts of a task, line by line
+pub async fn log_diff_read(
+    config: &Config,
+    dag_id: &str,
+    run_id: &str,
+    task_id: &str,
+    left_attempt: &u32,
+    right_attempt: &u32,
+    try_number: &u32,
+) -> Result<LogDiff, KyubeyError> {
+    // Only compare attempts the task has made
+    check_attempt(left_attempt, try_number)?;
+    check_attempt(right_attempt, try_number)?;
+
+    // Logs for both task attempts, without any coloring
+    let left: Vec<LogDiffLine> =
+        log_diff_lines(config, dag_id, run_id, task_id, left_attempt).await?;
+    let right: Vec<LogDiffLine> =
+        log_diff_lines(config, dag_id, run_id, task_id, right_attempt).await?;
+
+    // Align the logs on their normalized text
+    let left_keys: Vec<String> = left
+        .iter()
+        .map(|line: &LogDiffLine| normalize_log_line(&line.text))
+        .collect();
+    let right_keys: Vec<String> = right
+        .iter()
+        .map(|line: &LogDiffLine| normalize_log_line(&line.text))
+        .collect();
+    let deadline: Option<Instant> = Some(Instant::now() + LOG_DIFF_TIMEOUT);
+    let ops: Vec<DiffOp> =
+        capture_diff_slices_deadline(Algorithm::Patience, &left_keys, &right_keys, deadline);
+
+    // Turn the diff into side by side rows
+    let mut left = left.into_iter();
+    let mut right = right.into_iter();
+    let mut rows: Vec<LogDiffRow> = Vec::new();
+    for op in ops {
+        match op {
+            DiffOp::Equal { len, .. } => {
+                for _ in 0..len {
+                    rows.push(LogDiffRow {
+                        change: LogChange::Equal,
+                        left: left.next(),
+                        right: right.next(),
+                    });
+                }
+            }
+            DiffOp::Delete { old_len, .. } => {
+                for _ in 0..old_len {
+                    rows.push(LogDiffRow {
+                        change: LogChange::Removed,
+                        left: left.next(),
+                        right: None,
+                    });
+                }
+            }
+            DiffOp::Insert { new_len, .. } => {
+                for _ in 0..new_len {
+                    rows.push(LogDiffRow {
+                        change: LogChange::Added,
+                        left: None,
+                        right: right.next(),
+                    });
+                }
+            }
+            // Replaced lines share a row, anything left over is a plain add or remove
+            DiffOp::Replace {
+                old_len, new_len, ..
+            } => {
+                for index in 0..old_len.max(new_len) {
+                    let change: LogChange = match (index < old_len, index < new_len) {
+                        (true, true) => LogChange::Changed,
+                        (true, false) => LogChange::Removed,
+                        _ => LogChange::Added,
+                    };
+                    rows.push(LogDiffRow {
+                        change,
+                        left: if index < old_len { left.next() } else { None },
+                        right: if index < new_len { right.next() } else { None },
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(LogDiff {
+        left_attempt: *left_attempt,
+        right_attempt: *right_attempt,
+        rows,
+    })
+}
+
+/// Lines of a log ready to be diffed
+async fn log_diff_lines(
+    config: &Config,
+    dag_id: &str,
+    run_id: &str,
+    task_id: &str,
+    attepmt: &u32,
+) -> Result<Vec<LogDiffLine>, KyubeyError> {
+    let log: String = log_read(config, dag_id, run_id, task_id, attepmt).await?;
+
+    let lines: Vec<LogDiffLine> = parse_log(&log)
+        .into_iter()
+        .map(|line: LogLine| LogDiffLine {
+            line_number: line.line_number,
+            text: strip_ansi(&line.text),
+        })
+        .collect();
+
+    Ok(lines)
+}
+
+/// Size of each chunk of a log bundle sent to the client
+const LOG_BUNDLE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// How many chunks of a log bundle can wait on a slow client
+const LOG_BUNDLE_BACKLOG: usize = 16;
+
+/// Writer that hands everything written to it to an async channel
+struct ChannelWriter {
+    sender: mpsc::Sender<Result<Vec<u8>, io::Error>>,
+}
+
+impl Write for ChannelWriter {
+    /// Send a chunk, waiting if the client has fallen behind
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sender
+            .blocking_send(Ok(buf.to_vec()))
+            .map_err(|_| io::Error::from(ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    /// Nothing is held back, so nothing to flush
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Stream an archive of every attempt of every task in a Dag Run, plus a manifest
+pub async fn log_bundle_read(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    run_id: &str,
+    format: LogBundleFormat,
+) -> Result<ReceiverStream<Result<Vec<u8>, io::Error>>, KyubeyError> {
+    // Pull the Dag Run and its Tasks
+    let DagRunTasks { dag_run, tasks, .. } = tasks_for_dag_run_read(tx, config, run_id).await?;
+
+    // Build the archive on a blocking thread, sending it out as it is written
+    let (sender, receiver) = mpsc::channel(LOG_BUNDLE_BACKLOG);
+    let config: Config = config.clone();
+    spawn_blocking(move || {
+        let writer = ChannelWriter {
+            sender: sender.clone(),
+        };
+        if let Err(err) = write_log_bundle(writer, &config, dag_run, tasks, format) {
+            // Let the client know the archive is incomplete
+            let _ = sender.blocking_send(Err(err));
+        }
+    });
+
+    Ok(ReceiverStream::new(receiver))
+}
+
+/// Write a log bundle, reading each log straight from disk into the archive
+fn write_log_bundle(
+    writer: ChannelWriter,
+    config: &Config,
+    dag_run: DagRun,
+    tasks: Vec<Task>,
+    format: LogBundleFormat,
+) -> io::Result<()> {
+    let mut writer = BufWriter::with_capacity(LOG_BUNDLE_CHUNK_SIZE, writer);
+
+    // Every attempt we expect a log for, and the ones we found on disk
+    let mut files: Vec<LogBundleFile> = Vec::new();
+    let mut logs: Vec<(String, PathBuf, u64)> = Vec::new();
+    for task in &tasks {
+        for attempt in 1..=task.try_number.unwrap_or(0) {
+            let path: PathBuf = log_path(
+                config,
+                &dag_run.dag_id,
+                &dag_run.run_id,
+                &task.task_id,
+                &attempt,
+            );
+            let archive_path: String = format!(
+                "{}/{}/attempt={}.log",
+                dag_run.run_id, task.task_id, attempt
+            );
+            let size: Option<u64> = std::fs::metadata(&path).ok().map(|meta| meta.len());
+
+            if let Some(size) = size {
+                logs.push((archive_path.clone(), path, size));
+            }
+            files.push(LogBundleFile {
+                task_id: task.task_id.clone(),
+                attempt,
+                path: archive_path,
+                found: size.is_some(),
+            });
+        }
+    }
+
+    // Manifest goes first so it is easy to find
+    let manifest_path: String = format!("{}/manifest.json", dag_run.run_id);
+    let manifest_json: String = LogBundleManifest {
+        dag_run,
+        tasks,
+        files,
+    }
+    .to_json_string();
+
+    match format {
+        LogBundleFormat::Zip => {
+            let mut zip = ZipWriter::new_stream(&mut writer);
+            let options =
+                SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+            zip.start_file(manifest_path, options)?;
+            zip.write_all(manifest_json.as_bytes())?;
+
+            for (archive_path, path, size) in logs {
+                zip.start_file(archive_path, options.large_file(size > u64::from(u32::MAX)))?;
+                io::copy(&mut std::fs::File::open(path)?, &mut zip)?;
+            }
+
+            zip.finish()?;
+        }
+        LogBundleFormat::TarGz => {
+            let gzip = GzEncoder::new(&mut writer, Compression::default());
+            let mut tar = tar::Builder::new(gzip);
+            let mtime: u64 = u64::try_from(Utc::now().timestamp()).unwrap_or(0);
+
+            let mut header = tar::Header::new_gnu();
+            header.set_size(u64::try_from(manifest_json.len()).unwrap_or(u64::MAX));
+            header.set_mode(0o644);
+            header.set_mtime(mtime);
+            tar.append_data(&mut header, manifest_path, manifest_json.as_bytes())?;
+
+            for (archive_path, path, size) in logs {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(size);
+                header.set_mode(0o644);
+                header.set_mtime(mtime);
+                // Running tasks keep writing, so hand over exactly the size in the header, zero padded if it shrank
+                let log = std::fs::File::open(path)?
+                    .take(size)
+                    .chain(io::repeat(0))
+                    .take(size);
+                tar.append_data(&mut header, archive_path, log)?;
+            }
+
+            tar.into_inner()?.finish()?;
+        }
+    }
+
+    writer.flush()
+}
+
+/// How many calendar days a duration trend covers, today included
+const DURATION_TREND_DAYS: i64 = 30;
+
+/// How many days of history duration statistics look at
+const DURATION_DAYS: i64 = 90;
+
+/// A queued Dag Run is stuck after waiting this many seconds
+const STUCK_QUEUED_SECONDS: f64 = 2.0 * 60.0 * 60.0;
+
+/// Something is running long when it takes this many times longer than usual
+const LONG_RUNNING_FACTOR: f64 = 5.0;
+
+/// How many days of history we use to know what usual looks like
+const USUAL_DURATION_DAYS: i64 = 30;
+
+/// How many successful runs we need to see before we know what usual looks like
+const USUAL_DURATION_MIN_RUNS: i64 = 5;
+
+/// How many days of history flaky task detection looks at
+const FLAKY_TASK_DAYS: i64 = 30;
+
+/// How many runs a task needs before we call it flaky
+const FLAKY_TASK_MIN_RUNS: i64 = 5;
+
+/// How many days back failed Dag Runs stay in the failure queue
+const FAILURE_DAYS: i64 = 30;
+
+/// How many days back we look for SLA breaches to record
+const SLA_BREACH_DAYS: i64 = 7;
+
+/// A run is an outlier when it takes this many times longer than the median
+const DURATION_OUTLIER_FACTOR: f64 = 3.0;
+
+/// How many runs we need to see before calling anything an outlier
+const DURATION_OUTLIER_MIN_RUNS: usize = 5;
+
+/// Value at a percentile (0 to 1) of sorted values, interpolating between neighbours
+fn percentile(sorted: &[f64], percentile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let rank: f64 = percentile * (sorted.len() - 1) as f64;
+    let lower: usize = rank.floor() as usize;
+    let upper: usize = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Median, p90 and p99 of some durations
+fn duration_stats(durations: &[RunDuration]) -> DurationStats {
+    let mut sorted: Vec<f64> = durations
+        .iter()
+        .map(|run: &RunDuration| run.duration)
+        .collect();
+    sorted.sort_by(f64::total_cmp);
+
+    DurationStats {
+        runs: u64::try_from(sorted.len()).unwrap_or(u64::MAX),
+        median: percentile(&sorted, 0.5),
+        p90: percentile(&sorted, 0.9),
+        p99: percentile(&sorted, 0.99),
+    }
+}
+
+/// First day of a duration trend
+fn duration_trend_start(today: &NaiveDate) -> NaiveDate {
+    *today - TimeDelta::days(DURATION_TREND_DAYS - 1)
+}
+
+/// Median duration per day, for the days with runs since the start of the trend
+fn duration_trend(durations: &[RunDuration], start: &NaiveDate) -> Vec<DurationPoint> {
+    // Group durations by day
+    let mut days: BTreeMap<NaiveDate, Vec<f64>> = BTreeMap::new();
+    for run in durations {
+        let date: NaiveDate = run.execution_date.date_naive();
+        if date >= *start {
+            days.entry(date).or_default().push(run.duration);
+        }
+    }
+
+    days.into_iter()
+        .map(|(date, mut day)| {
+            day.sort_by(f64::total_cmp);
+            DurationPoint {
+                date,
+                median: percentile(&day, 0.5),
+            }
+        })
+        .collect()
+}
+
+/// Runs that took far longer than the median, latest first
+fn duration_outliers(durations: &[RunDuration], stats: &DurationStats) -> Vec<DurationOutlier> {
+    if durations.len() < DURATION_OUTLIER_MIN_RUNS {
+        return Vec::new();
+    }
+
+    durations
+        .iter()
+        .rev()
+        .filter(|run: &&RunDuration| run.duration > stats.median * DURATION_OUTLIER_FACTOR)
+        .map(|run: &RunDuration| DurationOutlier {
+            run_id: run.run_id.clone(),
+            execution_date: run.execution_date,
+            duration: run.duration,
+        })
+        .collect()
+}
+
+/// Median duration of a System's Dag Runs per day
+pub async fn system_duration_trend_read(
+    tx: &mut Transaction<'_, Postgres>,
+    system_id: &str,
+) -> Result<Vec<DurationPoint>, KyubeyError> {
+    // Only pull the days the trend will show
+    let start: NaiveDate = duration_trend_start(&Utc::now().date_naive());
+    let since: DateTime<Utc> = start.and_time(NaiveTime::MIN).and_utc();
+
+    // Pull how long each dag run took
+    let dag_runs: Vec<RunDuration> = dag_run_durations_by_system_select(tx, system_id, &since)
+        .await
+        .map_err(KyubeyError::Backend)?;
+
+    Ok(duration_trend(&dag_runs, &start))
+}
+
+/// Duration statistics for every Dag and Task of a System
+pub async fn system_durations_read(
+    tx: &mut Transaction<'_, Postgres>,
+    system_id: &str,
+) -> Result<SystemDurations, KyubeyError> {
+    // Pull the System
+    let system: System = system_read(tx, system_id).await?;
+
+    // Pull how long each recent dag run and task took
+    let since: DateTime<Utc> = Utc::now() - TimeDelta::days(DURATION_DAYS);
+    let dag_runs: Vec<RunDuration> = dag_run_durations_by_system_select(tx, system_id, &since)
+        .await
+        .map_err(KyubeyError::Backend)?;
+    let tasks: Vec<RunDuration> = task_durations_by_system_select(tx, system_id, &since)
+        .await
+        .map_err(KyubeyError::Backend)?;
+
+    // The System as a whole
+    let start: NaiveDate = duration_trend_start(&Utc::now().date_naive());
+    let trend: Vec<DurationPoint> = duration_trend(&dag_runs, &start);
+
+    // Group by Dag, and by Task within a Dag
+    let mut dag_groups: BTreeMap<String, Vec<RunDuration>> = BTreeMap::new();
+    for run in dag_runs {
+        dag_groups.entry(run.dag_id.clone()).or_default().push(run);
+    }
+    let mut task_groups: BTreeMap<(String, String), Vec<RunDuration>> = BTreeMap::new();
+    for run in tasks {
+        let task_id: String = run.task_id.clone().unwrap_or_default();
+        task_groups
+            .entry((run.dag_id.clone(), task_id))
+            .or_default()
+            .push(run);
+    }
+
+    let dags: Vec<DagDurations> = dag_groups
+        .into_iter()
+        .map(|(dag_id, runs)| {
+            let stats: DurationStats = duration_stats(&runs);
+            DagDurations {
+                dag_id,
+                trend: duration_trend(&runs, &start),
+                outliers: duration_outliers(&runs, &stats),
+                stats,
+            }
+        })
+        .collect();
+
+    let tasks: Vec<TaskDurations> = task_groups
+        .into_iter()
+        .map(|((dag_id, task_id), runs)| {
+            let stats: DurationStats = duration_stats(&runs);
+            TaskDurations {
+                dag_id,
+                task_id,
+                trend: duration_trend(&runs, &start),
+                outliers: duration_outliers(&runs, &stats),
+                stats,
+            }
+        })
+        .collect();
+
+    Ok(SystemDurations {
+        system,
+        trend,
+        dags,
+        tasks,
+    })
+}
+
+/// Seconds from each first failure to the next success, for runs of a single Dag in order
+fn recovery_times(outcomes: &[&RunOutcome]) -> Vec<f64> {
+    let mut recoveries: Vec<f64> = Vec::new();
+    let mut failed_at: Option<DateTime<Utc>> = None;
+
+    for outcome in outcomes {
+        let finished_at: DateTime<Utc> = outcome.end_date.unwrap_or(outcome.execution_date);
+        match (&outcome.state, failed_at) {
+            // Start of a failure streak
+            (Some(DagState::Failed), None) => failed_at = Some(finished_at),
+            // Back to a success
+            (Some(DagState::Success), Some(since)) => {
+                recoveries.push((finished_at - since).as_seconds_f64().max(0.0));
+                failed_at = None;
+            }
+            _ => {}
+        }
+    }
+
+    recoveries
+}
+
+/// Roll up outcomes and recovery times into reliability numbers
+fn reliability(outcomes: &[&RunOutcome], recoveries: &[f64]) -> Reliability {
+    let count = |state: DagState| -> u64 {
+        let matches = outcomes
+            .iter()
+            .filter(|outcome: &&&RunOutcome| outcome.state.as_ref() == Some(&state))
+            .count();
+        u64::try_from(matches).unwrap_or(u64::MAX)
+    };
+    let successes: u64 = count(DagState::Success);
+    let failures: u64 = count(DagState::Failed);
+    let finished: u64 = successes + failures;
+
+    let tasks: u64 = outcomes.iter().map(|outcome| outcome.tasks).sum();
+    let retried_tasks: u64 = outcomes.iter().map(|outcome| outcome.retried_tasks).sum();
+
+    let rate = |part: u64, whole: u64| -> Option<f64> {
+        match whole {
+            0 => None,
+            _ => Some(part as f64 / whole as f64),
+        }
+    };
+
+    Reliability {
+        runs: u64::try_from(outcomes.len()).unwrap_or(u64::MAX),
+        successes,
+        failures,
+        success_rate: rate(successes, finished),
+        failure_rate: rate(failures, finished),
+        mean_time_to_recovery: match recoveries.is_empty() {
+            true => None,
+            false => Some(recoveries.iter().sum::<f64>() / recoveries.len() as f64),
+        },
+        task_retry_rate: rate(retried_tasks, tasks),
+    }
+}
+
+/// Reliability of every System and Dag over a window, least reliable System first
+pub async fn scorecard_read(
+    tx: &mut Transaction<'_, Postgres>,
+    window: &ScorecardWindow,
+) -> Result<Vec<SystemScorecard>, KyubeyError> {
+    // Pull every dag run in the window
+    let since: DateTime<Utc> = Utc::now() - TimeDelta::days(window.days());
+    let outcomes: Vec<RunOutcome> = dag_run_outcomes_select(tx, &since)
+        .await
+        .map_err(KyubeyError::Backend)?;
+
+    // Group by System, then by Dag. Outcomes come in execution order.
+    let mut systems: BTreeMap<&str, BTreeMap<&str, Vec<&RunOutcome>>> = BTreeMap::new();
+    for outcome in &outcomes {
+        systems
+            .entry(&outcome.system_id)
+            .or_default()
+            .entry(&outcome.dag_id)
+            .or_default()
+            .push(outcome);
+    }
+
+    let mut scorecards: Vec<SystemScorecard> = systems
+        .into_values()
+        .filter_map(|dags| {
+            let mut system_outcomes: Vec<&RunOutcome> = Vec::new();
+            let mut system_recoveries: Vec<f64> = Vec::new();
+
+            let dags: Vec<DagScorecard> = dags
+                .into_iter()
+                .map(|(dag_id, outcomes)| {
+                    let recoveries: Vec<f64> = recovery_times(&outcomes);
+                    let reliability: Reliability = reliability(&outcomes, &recoveries);
+                    system_outcomes.extend(outcomes);
+                    system_recoveries.extend(recoveries);
+                    DagScorecard {
+                        dag_id: dag_id.to_string(),
+                        reliability,
+                    }
+                })
+                .collect();
+
+            let first: &RunOutcome = system_outcomes.first()?;
+            Some(SystemScorecard {
+                client_name: first.client_name.clone(),
+                client_id: first.client_id.clone(),
+                system_name: first.system_name.clone(),
+                system_id: first.system_id.clone(),
+                reliability: reliability(&system_outcomes, &system_recoveries),
+                dags,
+            })
+        })
+        .collect();
+
+    // Systems with nothing finished yet have no track record, so they go last
+    scorecards.sort_by(|left, right| {
+        let (left, right) = (
+            left.reliability.success_rate,
+            right.reliability.success_rate,
+        );
+        left.is_none()
+            .cmp(&right.is_none())
+            .then_with(|| left.unwrap_or(0.0).total_cmp(&right.unwrap_or(0.0)))
+    });
+
+    Ok(scorecards)
+}
+
+/// First day shown on the Dag Run calendar, always a Sunday so weeks line up
+pub fn calendar_start(today: &NaiveDate) -> NaiveDate {
+    let sunday: NaiveDate = *today - TimeDelta::days(today.weekday().num_days_from_sunday().into());
+    sunday - TimeDelta::weeks(CALENDAR_WEEKS - 1)
+}
+
+/// Daily Dag Run counts and worst states for a System's calendar
+pub async fn system_calendar_read(
+    tx: &mut Transaction<'_, Postgres>,
+    system_id: &str,
+) -> Result<Vec<CalendarDay>, KyubeyError> {
+    // Only pull the days the calendar will show
+    let since: DateTime<Utc> = calendar_start(&Utc::now().date_naive())
+        .and_time(NaiveTime::MIN)
+        .and_utc();
+
+    dag_run_calendar_by_system_select(tx, system_id, &since)
+        .await
+        .map_err(KyubeyError::Backend)
+}
+
+/// Tasks of a System that needed retries recently, flakiest first
+pub async fn system_flaky_tasks_read(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    system_id: &str,
+) -> Result<SystemFlakyTasks, KyubeyError> {
+    // Pull the System
+    let system: System = system_read(tx, system_id).await?;
+
+    // Rank the tasks that needed retries
+    let since: DateTime<Utc> = Utc::now() - TimeDelta::days(FLAKY_TASK_DAYS);
+    let mut tasks: Vec<FlakyTask> =
+        flaky_tasks_by_system_select(tx, system_id, &since, &FLAKY_TASK_MIN_RUNS)
+            .await
+            .map_err(KyubeyError::Backend)?;
+
+    // Airflow's own record of failures beats working them out, if it still keeps one
+    if config.airflow_task_fail {
+        let failures: HashMap<(String, String), u64> =
+            task_failures_by_system_select(tx, system_id, &since)
+                .await
+                .map_err(KyubeyError::Backend)?;
+        for task in &mut tasks {
+            task.failed_attempts = failures
+                .get(&(task.dag_id.clone(), task.task_id.clone()))
+                .copied()
+                .unwrap_or_default();
+        }
+        tasks.sort_by(|a: &FlakyTask, b: &FlakyTask| {
+            b.retry_rate
+                .total_cmp(&a.retry_rate)
+                .then_with(|| b.failed_attempts.cmp(&a.failed_attempts))
+                .then_with(|| a.dag_id.cmp(&b.dag_id))
+                .then_with(|| a.task_id.cmp(&b.task_id))
+        });
+    }
+
+    Ok(SystemFlakyTasks { system, tasks })
+}
+
+/// Check an in-flight Dag Run or Task against how long it usually takes
+fn detect_anomaly(
+    active: ActiveRun,
+    usual: &HashMap<(String, Option<String>), f64>,
+    now: &DateTime<Utc>,
+) -> Option<Anomaly> {
+    // Queued Dag Runs are stuck after a fixed wait, anything running is held to its own history
+    let (kind, since, usual): (AnomalyKind, DateTime<Utc>, Option<f64>) =
+        match (&active.task_id, active.state.as_str()) {
+            (None, "queued") => (AnomalyKind::StuckQueued, active.queued_at?, None),
+            (None, "running") => (
+                AnomalyKind::LongRunningDagRun,
+                active.start_date?,
+                Some(*usual.get(&(active.dag_id.clone(), None))?),
+            ),
+            (Some(task_id), "running") => (
+                AnomalyKind::LongRunningTask,
+                active.start_date?,
+                Some(*usual.get(&(active.dag_id.clone(), Some(task_id.clone())))?),
+            ),
+            _ => return None,
+        };
+
+    let elapsed: f64 = (*now - since).as_seconds_f64();
+    let limit: f64 = match usual {
+        Some(median) => median * LONG_RUNNING_FACTOR,
+        None => STUCK_QUEUED_SECONDS,
+    };
+    if elapsed <= limit {
+        return None;
+    }
+
+    Some(Anomaly {
+        kind,
+        client_name: active.client_name,
+        client_id: active.client_id,
+        system_name: active.system_name,
+        system_id: active.system_id,
+        dag_id: active.dag_id,
+        run_id: active.run_id,
+        task_id: active.task_id,
+        since,
+        elapsed,
+        usual,
+    })
+}
+
+/// Stuck Dag Runs and long running Dag Runs and Tasks across every System, longest first
+pub async fn attention_read(
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<Vec<Anomaly>, KyubeyError> {
+    // Pull everything in flight
+    let mut active: Vec<ActiveRun> = active_dag_runs_select(tx)
+        .await
+        .map_err(KyubeyError::Backend)?;
+    active.extend(
+        active_tasks_select(tx)
+            .await
+            .map_err(KyubeyError::Backend)?,
+    );
+
+    // Pull what usual looks like for every Dag and Task
+    let since: DateTime<Utc> = Utc::now() - TimeDelta::days(USUAL_DURATION_DAYS);
+    let mut usual: Vec<UsualDuration> =
+        usual_dag_run_durations_select(tx, &since, &USUAL_DURATION_MIN_RUNS)
+            .await
+            .map_err(KyubeyError::Backend)?;
+    usual.extend(
+        usual_task_durations_select(tx, &since, &USUAL_DURATION_MIN_RUNS)
+            .await
+            .map_err(KyubeyError::Backend)?,
+    );
+    let usual: HashMap<(String, Option<String>), f64> = usual
+        .into_iter()
+        .map(|duration: UsualDuration| ((duration.dag_id, duration.task_id), duration.median))
+        .collect();
+
+    // Keep only what is waiting or running too long
+    let now: DateTime<Utc> = Utc::now();
+    let mut anomalies: Vec<Anomaly> = active
+        .into_iter()
+        .filter_map(|active: ActiveRun| detect_anomaly(active, &usual, &now))
+        .collect();
+    anomalies.sort_by(|left, right| right.elapsed.total_cmp(&left.elapsed));
+
+    Ok(anomalies)
+}
+
+/// Make sure a NoteTarget names a System, Dag Run or Task attempt, without looking any of them up
+fn check_note_target(target: &NoteTarget) -> Result<(), KyubeyError> {
+    let bad_request = |message: &str| KyubeyError::InvalidInput(message.to_string());
+
+    match target {
+        NoteTarget {
+            task_id: None,
+            attempt: Some(_),
+            ..
+        } => Err(bad_request("An attempt needs a Task ID")),
+        NoteTarget {
+            run_id: None,
+            task_id: Some(_),
+            ..
+        } => Err(bad_request("A Task needs a Run ID")),
+        NoteTarget {
+            system_id: None,
+            run_id: None,
+            ..
+        } => Err(bad_request("A note needs a System ID or Run ID")),
+        NoteTarget { .. } => Ok(()),
+    }
+}
+
+/// Make sure a NoteTarget points at something real, and find the System it belongs to
+async fn note_target_system(
+    tx: &mut Transaction<'_, Postgres>,
+    target: &NoteTarget,
+) -> Result<String, KyubeyError> {
+    let bad_request = |message: &str| KyubeyError::InvalidInput(message.to_string());
+    check_note_target(target)?;
+
+    match target {
+        NoteTarget {
+            run_id: Some(run_id),
+            system_id,
+            ..
+        } => {
+            let system: System = system_for_dag_run_read(tx, run_id).await?;
+            match system_id {
+                Some(system_id) if *system_id != system.system_id => {
+                    Err(bad_request("Dag Run does not belong to that System"))
+                }
+                _ => Ok(system.system_id),
+            }
+        }
+        NoteTarget {
+            system_id: Some(system_id),
+            ..
+        } => Ok(system_read(tx, system_id).await?.system_id),
+        NoteTarget { .. } => Err(bad_request("A note needs a System ID or Run ID")),
+    }
+}
+
+/// Notes left on a System, Dag Run or Task attempt, from Kyubey and Airflow, oldest first
+pub async fn notes_read(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    target: &NoteTarget,
+) -> Result<Vec<Note>, KyubeyError> {
+    // Reading needs no lookups, so Dag Runs no System claims still show their notes
+    check_note_target(target)?;
+    let attempt: Option<i32> = target.attempt.map(|attempt: u32| attempt as i32);
+
+    // Notes left in Kyubey, if we have somewhere to keep them
+    let mut notes: Vec<Note> = Vec::new();
+    if config.kyubey_schema {
+        notes.extend(
+            notes_select(
+                tx,
+                &target.system_id,
+                &target.run_id,
+                &target.task_id,
+                &attempt,
+            )
+            .await
+            .map_err(KyubeyError::Backend)?,
+        );
+    }
+
+    // Notes left in Airflow, which are per Dag Run or Task rather than per attempt
+    if config.airflow_notes {
+        let airflow_notes: Vec<Note> = match (&target.run_id, &target.task_id) {
+            (Some(run_id), None) => dag_run_airflow_notes_select(tx, run_id).await,
+            (Some(run_id), Some(task_id)) => task_airflow_notes_select(tx, run_id, task_id).await,
+            _ => Ok(Vec::new()),
+        }
+        .map_err(KyubeyError::Backend)?;
+        notes.extend(airflow_notes);
+    }
+
+    notes.sort_by_key(|note: &Note| note.created_at);
+
+    Ok(notes)
+}
+
+/// Leave a note on a System, Dag Run or Task attempt
+pub async fn note_create(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    new_note: &NewNote,
+) -> Result<Note, KyubeyError> {
+    // Notes live in Kyubey's own schema
+    require_kyubey_schema(config, "Writing notes")?;
+
+    let author: &str = new_note.author.trim();
+    let body: &str = new_note.body.trim();
+    if author.is_empty() || body.is_empty() {
+        return Err(KyubeyError::InvalidInput(
+            "A note needs an author and a body".to_string(),
+        ));
+    }
+
+    let target: NoteTarget = new_note.target();
+    let system_id: String = note_target_system(tx, &target).await?;
+    let attempt: Option<i32> = target.attempt.map(|attempt: u32| attempt as i32);
+
+    note_insert(
+        tx,
+        &system_id,
+        &target.run_id,
+        &target.task_id,
+        &attempt,
+        author,
+        body,
+    )
+    .await
+    .map_err(KyubeyError::Backend)
+}
+
+/// Recent failed Dag Runs across all Systems, newest first, filtered by status and assignee
+pub async fn failures_read(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    status: &Option<FailureStatus>,
+    assignee: &Option<String>,
+) -> Result<Vec<Failure>, KyubeyError> {
+    // Failures are tracked in Kyubey's own schema
+    require_kyubey_schema(config, "Tracking failures")?;
+
+    // Pull every recent failure that matches
+    let since: DateTime<Utc> = Utc::now() - TimeDelta::days(FAILURE_DAYS);
+    failures_select(tx, &since, status, assignee)
+        .await
+        .map_err(KyubeyError::Backend)
+}
+
+/// Acknowledge, assign or resolve a failed Dag Run
+pub async fn failure_update(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    run_id: &str,
+    action: &FailureAction,
+    update: &FailureUpdate,
+) -> Result<Failure, KyubeyError> {
+    // Failures are tracked in Kyubey's own schema
+    require_kyubey_schema(config, "Tracking failures")?;
+
+    let bad_request = |message: &str| KyubeyError::InvalidInput(message.to_string());
+    let by: &str = update.by.trim();
+    if by.is_empty() {
+        return Err(bad_request("Someone needs to be acting on the failure"));
+    }
+
+    // Only failed Dag Runs can be looked after
+    failure_select(tx, run_id).await.map_err(|err| match err {
+        sqlx::Error::RowNotFound => KyubeyError::not_found(Entity::FailedDagRun, run_id),
+        err => KyubeyError::Backend(err),
+    })?;
+
+    // A Dag Run that failed again needs looking after again
+    failure_stale_clear(tx, run_id)
+        .await
+        .map_err(KyubeyError::Backend)?;
+
+    match action {
+        FailureAction::Acknowledge => failure_acknowledge_upsert(tx, run_id, by).await,
+        FailureAction::Assign => {
+            let assignee: &str = update.assignee.as_deref().unwrap_or_default().trim();
+            if assignee.is_empty() {
+                return Err(bad_request("A failure needs someone to be assigned to"));
+            }
+            failure_assign_upsert(tx, run_id, assignee).await
+        }
+        FailureAction::Resolve => failure_resolve_upsert(tx, run_id, by).await,
+    }
+    .map_err(KyubeyError::Backend)?;
+
+    failure_select(tx, run_id)
+        .await
+        .map_err(KyubeyError::Backend)
+}
+
+/// Every saved search, by name
+pub async fn saved_searches_read(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+) -> Result<Vec<SavedSearch>, KyubeyError> {
+    // Saved searches live in Kyubey's own schema
+    require_kyubey_schema(config, "Saving searches")?;
+
+    saved_searches_select(tx)
+        .await
+        .map_err(KyubeyError::Backend)
+}
+
+/// Save a search under a name, replacing any search already saved under it
+pub async fn saved_search_save(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    new_saved_search: &NewSavedSearch,
+) -> Result<SavedSearch, KyubeyError> {
+    // Saved searches live in Kyubey's own schema
+    require_kyubey_schema(config, "Saving searches")?;
+
+    let name: &str = new_saved_search.name.trim();
+    if name.is_empty() {
+        return Err(KyubeyError::InvalidInput(
+            "A saved search needs a name".to_string(),
+        ));
+    }
+
+    saved_search_upsert(tx, name, new_saved_search.search_by.trim())
+        .await
+        .map_err(KyubeyError::Backend)
+}
+
+/// Forget a saved search
+pub async fn saved_search_remove(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    name: &str,
+) -> Result<(), KyubeyError> {
+    // Saved searches live in Kyubey's own schema
+    require_kyubey_schema(config, "Saving searches")?;
+
+    match saved_search_delete(tx, name).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(KyubeyError::not_found(Entity::SavedSearch, name)),
+        Err(err) => Err(KyubeyError::Backend(err)),
+    }
+}
+
+/// Fail features that keep their data in Kyubey's own schema when it is switched off
+pub fn require_kyubey_schema(config: &Config, feature: &str) -> Result<(), KyubeyError> {
+    match config.kyubey_schema {
+        true => Ok(()),
+        false => Err(KyubeyError::Unavailable(format!(
+            "{feature} needs Kyubey's own schema, which is off for this environment, \
+             either by the kyubey_schema setting or because its database is read-only"
+        ))),
+    }
+}
+
+/// Longest we wait on any one readiness check, so probes answer quickly
+const READINESS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Check every environment at once
+pub async fn readiness_read(environments: &[Environment]) -> Readiness {
+    let mut checks: JoinSet<(usize, EnvironmentReadiness)> = JoinSet::new();
+    for (index, environment) in environments.iter().enumerate() {
+        let environment: Environment = environment.clone();
+        checks.spawn(async move { (index, environment_readiness_read(&environment).await) });
+    }
+
+    // Keep environments in the order they were configured
+    let mut readiness: Vec<(usize, EnvironmentReadiness)> = checks.join_all().await;
+    readiness.sort_by_key(|(index, _)| *index);
+    let environments: Vec<EnvironmentReadiness> = readiness
+        .into_iter()
+        .map(|(_, readiness)| readiness)
+        .collect();
+
+    // One environment being down shouldn't take the others out of rotation with it
+    Readiness {
+        ready: environments
+            .iter()
+            .any(|readiness: &EnvironmentReadiness| readiness.ready),
+        environments,
+    }
+}
+
+/// A schema from a newer build is fine mid rollout, only one behind what this build needs is not
+fn kyubey_schema_check(version: Option<i64>, expected: Option<i64>) -> Result<String, String> {
+    let version: i64 = version.unwrap_or_default();
+    let expected: i64 = expected.unwrap_or_default();
+    match version.cmp(&expected) {
+        Ordering::Equal => Ok(format!("Airflow tables found, Kyubey schema at {version}")),
+        Ordering::Greater => Ok(format!(
+            "Airflow tables found, Kyubey schema at {version}, newer than {expected} this build knows"
+        )),
+        Ordering::Less => Err(format!("Kyubey schema at {version}, expected {expected}")),
+    }
+}
+
+/// Check the database, logs and schema of an environment, turning every failure into a failed check
+async fn environment_readiness_read(environment: &Environment) -> EnvironmentReadiness {
+    let Environment { config, pool } = environment;
+    let timed_out = |_| format!("No answer within {}s", READINESS_TIMEOUT.as_secs());
+
+    // Database is reachable, and how long a round trip takes
+    let started: Instant = Instant::now();
+    let database: Check = match tokio::time::timeout(READINESS_TIMEOUT, ping_select(pool))
+        .await
+        .map_err(timed_out)
+        .and_then(|result| result.map_err(|err: sqlx::Error| err.to_string()))
+    {
+        Ok(()) => Check {
+            ok: true,
+            latency_ms: Some(started.elapsed().as_secs_f64() * 1000.0),
+            detail: "Reachable".to_string(),
+        },
+        Err(detail) => Check {
+            ok: false,
+            latency_ms: None,
+            detail,
+        },
+    };
+
+    // Log folder can be listed
+    let logs: Check = match tokio::time::timeout(READINESS_TIMEOUT, fs::read_dir(&config.log_path))
+        .await
+        .map_err(timed_out)
+        .and_then(|result| result.map_err(|err: io::Error| err.to_string()))
+    {
+        Ok(_) => Check {
+            ok: true,
+            latency_ms: None,
+            detail: format!("{} is readable", config.log_path),
+        },
+        Err(detail) => Check {
+            ok: false,
+            latency_ms: None,
+            detail: format!("{} is not readable: {}", config.log_path, detail),
+        },
+    };
+
+    // Airflow has the tables we read, and Kyubey's schema is one this build knows
+    let schema = async {
+        let missing: Vec<String> = airflow_tables_missing_select(pool)
+            .await
+            .map_err(|err: sqlx::Error| err.to_string())?;
+        if !missing.is_empty() {
+            return Err(format!("Airflow is missing {}", missing.join(", ")));
+        }
+        if !config.kyubey_schema {
+            return Ok("Airflow tables found".to_string());
+        }
+
+        let version: Option<i64> = kyubey_schema_version_select(pool)
+            .await
+            .map_err(|err: sqlx::Error| err.to_string())?;
+        kyubey_schema_check(version, kyubey_schema_latest_version())
+    };
+    let schema: Check = match tokio::time::timeout(READINESS_TIMEOUT, schema)
+        .await
+        .map_err(timed_out)
+        .and_then(|result| result)
+    {
+        Ok(detail) => Check {
+            ok: true,
+            latency_ms: None,
+            detail,
+        },
+        Err(detail) => Check {
+            ok: false,
+            latency_ms: None,
+            detail,
+        },
+    };
+
+    EnvironmentReadiness {
+        environment: config.environment.clone(),
+        ready: database.ok && logs.ok && schema.ok,
+        database,
+        logs,
+        schema,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_log_line_masks_timestamps() {
+        assert_eq!(
+            normalize_log_line("[2025-05-18T12:00:00.123+0000] {file.py:1} INFO - started"),
+            "[<timestamp>] {file.py:1} INFO - started"
+        );
+        assert_eq!(
+            normalize_log_line("at 2025-05-18 12:00:00,123 and 2025-05-19T01:02:03Z"),
+            "at <timestamp> and <timestamp>"
+        );
+        assert_eq!(
+            normalize_log_line("offset 2025-05-18T12:00:00+01:00"),
+            "offset <timestamp>"
+        );
+    }
+
+    #[test]
+    fn normalize_log_line_masks_pids() {
+        assert_eq!(
+            normalize_log_line("Started process 4242 to run task"),
+            "Started process <pid> to run task"
+        );
+        assert_eq!(
+            normalize_log_line("pid=12 PID: 34 pid 56"),
+            "pid=<pid> PID: <pid> pid <pid>"
+        );
+        // Numbers that are not process ids stay put
+        assert_eq!(normalize_log_line("rapid 100 rows"), "rapid 100 rows");
+    }
+
+    #[test]
+    fn normalize_log_line_ignores_ansi_colors() {
+        assert_eq!(
+            normalize_log_line("\u{1b}[31mpid=7\u{1b}[0m"),
+            normalize_log_line("pid=8")
+        );
+    }
+
+    #[test]
+    fn check_attempt_accepts_only_made_attempts() {
+        assert!(check_attempt(&1, &3).is_ok());
+        assert!(check_attempt(&3, &3).is_ok());
+        assert!(matches!(
+            check_attempt(&0, &3),
+            Err(KyubeyError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            check_attempt(&4, &3),
+            Err(KyubeyError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn percentile_interpolates_between_neighbours() {
+        let sorted: [f64; 4] = [1.0, 2.0, 3.0, 4.0];
+        assert_eq!(percentile(&sorted, 0.0), 1.0);
+        assert_eq!(percentile(&sorted, 0.5), 2.5);
+        assert_eq!(percentile(&sorted, 1.0), 4.0);
+        assert!((percentile(&sorted, 0.9) - 3.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn percentile_handles_tiny_inputs() {
+        assert_eq!(percentile(&[], 0.5), 0.0);
+        assert_eq!(percentile(&[7.0], 0.0), 7.0);
+        assert_eq!(percentile(&[7.0], 0.99), 7.0);
+    }
+
+    /// A successful run of a Dag taking some seconds
+    fn run_duration(execution_date: &str, duration: f64) -> RunDuration {
+        RunDuration {
+            dag_id: "dag".to_string(),
+            task_id: None,
+            run_id: execution_date.to_string(),
+            execution_date: DateTime::parse_from_rfc3339(execution_date)
+                .expect("valid date")
+                .to_utc(),
+            duration,
+        }
+    }
+
+    #[test]
+    fn duration_trend_covers_calendar_days_from_the_start() {
+        let runs: Vec<RunDuration> = vec![
+            run_duration("2025-04-01T00:00:00Z", 100.0),
+            run_duration("2025-05-01T00:00:00Z", 10.0),
+            run_duration("2025-05-01T12:00:00Z", 30.0),
+            run_duration("2025-05-03T00:00:00Z", 5.0),
+        ];
+        let start: NaiveDate = duration_trend_start(&NaiveDate::from_ymd_opt(2025, 5, 30).unwrap());
+        assert_eq!(start, NaiveDate::from_ymd_opt(2025, 5, 1).unwrap());
+
+        let trend: Vec<DurationPoint> = duration_trend(&runs, &start);
+        let points: Vec<(NaiveDate, f64)> = trend
+            .iter()
+            .map(|point: &DurationPoint| (point.date, point.median))
+            .collect();
+        assert_eq!(
+            points,
+            vec![
+                (NaiveDate::from_ymd_opt(2025, 5, 1).unwrap(), 20.0),
+                (NaiveDate::from_ymd_opt(2025, 5, 3).unwrap(), 5.0),
+            ]
+        );
+    }
+
+    /// A Dag Run of one System finishing some minutes into the day
+    fn run_outcome(minute: i64, state: Option<DagState>, end: bool) -> RunOutcome {
+        let execution_date: DateTime<Utc> = DateTime::parse_from_rfc3339("2025-05-18T00:00:00Z")
+            .expect("valid date")
+            .to_utc();
+
+        RunOutcome {
+            client_name: "Acme".to_string(),
+            client_id: "c1".to_string(),
+            system_name: "Billing".to_string(),
+            system_id: "s1".to_string(),
+            dag_id: "dag".to_string(),
+            execution_date,
+            end_date: end.then(|| execution_date + TimeDelta::minutes(minute)),
+            state,
+            tasks: 2,
+            retried_tasks: 1,
+        }
+    }
+
+    #[test]
+    fn recovery_times_measure_from_first_failure_to_next_success() {
+        let outcomes: Vec<RunOutcome> = vec![
+            run_outcome(1, Some(DagState::Success), true),
+            run_outcome(10, Some(DagState::Failed), true),
+            run_outcome(20, Some(DagState::Failed), true),
+            run_outcome(25, Some(DagState::Running), false),
+            run_outcome(40, Some(DagState::Success), true),
+            run_outcome(50, Some(DagState::Failed), true),
+        ];
+        let outcomes: Vec<&RunOutcome> = outcomes.iter().collect();
+
+        // One recovery of 30 minutes, the last failure has not recovered yet
+        assert_eq!(recovery_times(&outcomes), vec![30.0 * 60.0]);
+    }
+
+    #[test]
+    fn recovery_times_fall_back_to_execution_date_and_never_go_negative() {
+        let outcomes: Vec<RunOutcome> = vec![
+            run_outcome(10, Some(DagState::Failed), true),
+            run_outcome(0, Some(DagState::Success), false),
+        ];
+        let outcomes: Vec<&RunOutcome> = outcomes.iter().collect();
+
+        assert_eq!(recovery_times(&outcomes), vec![0.0]);
+        assert!(recovery_times(&[]).is_empty());
+    }
+
+    #[test]
+    fn reliability_leaves_rates_empty_until_runs_finish() {
+        let outcomes: Vec<RunOutcome> = vec![
+            run_outcome(0, Some(DagState::Running), false),
+            run_outcome(0, Some(DagState::Queued), false),
+        ];
+        let outcomes: Vec<&RunOutcome> = outcomes.iter().collect();
+        let reliability: Reliability = reliability(&outcomes, &[]);
+
+        assert_eq!(reliability.runs, 2);
+        assert_eq!(reliability.success_rate, None);
+        assert_eq!(reliability.failure_rate, None);
+        assert_eq!(reliability.mean_time_to_recovery, None);
+        assert_eq!(reliability.task_retry_rate, Some(0.5));
+    }
+
+    /// A Dag Run for System s1 executed at midnight and started a minute later
+    fn sla_dag_run(state: Option<DagState>, end_minute: Option<i64>) -> DagRun {
+        let execution_date: DateTime<Utc> = DateTime::parse_from_rfc3339("2025-05-18T00:00:00Z")
+            .expect("valid date")
+            .to_utc();
+
+        DagRun {
+            dag_id: "dag".to_string(),
+            execution_date,
+            run_id: "run".to_string(),
+            system_id: Some("s1".to_string()),
+            state,
+            start_date: Some(execution_date + TimeDelta::minutes(1)),
+            end_date: end_minute.map(|minute: i64| execution_date + TimeDelta::minutes(minute)),
+            sla_status: None,
+        }
+    }
+
+    fn sla(dag_id: Option<&str>, max_duration: Option<u32>, deadline: Option<u32>) -> Sla {
+        Sla {
+            system_id: "s1".to_string(),
+            dag_id: dag_id.map(str::to_string),
+            max_duration_minutes: max_duration,
+            deadline_minutes: deadline,
+        }
+    }
+
+    /// Minutes after the execution date
+    fn at(minute: i64) -> DateTime<Utc> {
+        sla_dag_run(None, None).execution_date + TimeDelta::minutes(minute)
+    }
+
+    #[test]
+    fn sla_status_judges_successful_runs_by_when_they_ended() {
+        let sla: Sla = sla(None, None, Some(30));
+        let on_time: DagRun = sla_dag_run(Some(DagState::Success), Some(30));
+        let late: DagRun = sla_dag_run(Some(DagState::Success), Some(31));
+
+        // Finished runs keep their status however late we look
+        assert!(sla.status(&on_time, &at(600)) == SlaStatus::Met);
+        assert!(sla.status(&late, &at(31)) == SlaStatus::Breached);
+    }
+
+    #[test]
+    fn sla_status_breaches_unfinished_runs_once_due() {
+        let sla: Sla = sla(None, None, Some(30));
+        let running: DagRun = sla_dag_run(Some(DagState::Running), None);
+        let failed: DagRun = sla_dag_run(Some(DagState::Failed), Some(10));
+
+        assert!(sla.status(&running, &at(30)) == SlaStatus::Pending);
+        assert!(sla.status(&running, &at(31)) == SlaStatus::Breached);
+
+        // A failed run never finished as far as the SLA is concerned
+        assert!(sla.status(&failed, &at(20)) == SlaStatus::Pending);
+        assert!(sla.status(&failed, &at(31)) == SlaStatus::Breached);
+    }
+
+    #[test]
+    fn sla_status_holds_runs_to_the_tighter_limit() {
+        // Started a minute in, so 10 minutes of runtime is due at minute 11
+        let sla: Sla = sla(None, Some(10), Some(30));
+        let run: DagRun = sla_dag_run(Some(DagState::Success), Some(20));
+        assert!(sla.status(&run, &at(20)) == SlaStatus::Breached);
+
+        // A max duration means nothing until the run starts
+        let queued: DagRun = DagRun {
+            start_date: None,
+            ..sla_dag_run(Some(DagState::Queued), None)
+        };
+        assert!(self::sla(None, Some(10), None).status(&queued, &at(600)) == SlaStatus::Pending);
+    }
+
+    #[test]
+    fn sla_for_prefers_a_dags_own_sla() {
+        let slas: Vec<Sla> = vec![sla(None, None, Some(30)), sla(Some("dag"), None, Some(60))];
+        let run: DagRun = sla_dag_run(Some(DagState::Success), Some(45));
+        assert!(sla_status(&slas, &run, &at(45)) == Some(SlaStatus::Met));
+
+        let other: DagRun = DagRun {
+            dag_id: "other".to_string(),
+            ..sla_dag_run(Some(DagState::Success), Some(45))
+        };
+        assert!(sla_status(&slas, &other, &at(45)) == Some(SlaStatus::Breached));
+
+        let elsewhere: DagRun = DagRun {
+            system_id: Some("s2".to_string()),
+            ..sla_dag_run(Some(DagState::Success), Some(45))
+        };
+        assert!(sla_status(&slas, &elsewhere, &at(45)).is_none());
+    }
+
+    /// Style after applying SGR parameters to the default style
+    fn sgr(params: &str) -> AnsiStyle {
+        let mut style: AnsiStyle = AnsiStyle::default();
+        style.apply_sgr(params);
+        style
+    }
+
+    #[test]
+    fn apply_sgr_sets_and_resets_attributes() {
+        let style: AnsiStyle = sgr("1;4;31;42");
+        assert!(style.bold && style.underline && !style.italic);
+        assert!(style.foreground == Some(AnsiColor::Red));
+        assert!(style.background == Some(AnsiColor::Green));
+
+        let mut reset: AnsiStyle = style;
+        reset.apply_sgr("");
+        assert!(reset == AnsiStyle::default());
+        reset = style;
+        reset.apply_sgr("0");
+        assert!(reset == AnsiStyle::default());
+        reset = style;
+        reset.apply_sgr("22;24;39;49");
+        assert!(reset == AnsiStyle::default());
+    }
+
+    #[test]
+    fn apply_sgr_maps_bright_colors() {
+        assert!(sgr("91").foreground == Some(AnsiColor::BrightRed));
+        assert!(sgr("107").background == Some(AnsiColor::BrightWhite));
+    }
+
+    #[test]
+    fn apply_sgr_handles_256_color_palette() {
+        assert!(sgr("38;5;1").foreground == Some(AnsiColor::Red));
+        assert!(sgr("48;5;12").background == Some(AnsiColor::BrightBlue));
+        assert!(sgr("38:5:9").foreground == Some(AnsiColor::BrightRed));
+
+        // Beyond the 16 base colors we fall back to the default color
+        let style: AnsiStyle = sgr("38;5;196;1");
+        assert!(style.foreground.is_none());
+        assert!(style.bold);
+    }
+
+    #[test]
+    fn apply_sgr_skips_true_color_components() {
+        // The r;g;b values must not be read as codes, 1 here would otherwise be bold
+        let style: AnsiStyle = sgr("38;2;1;4;3;32");
+        assert!(!style.bold && !style.underline && !style.italic);
+        assert!(style.foreground == Some(AnsiColor::Green));
+
+        let style: AnsiStyle = sgr("31;48;2;255;0;0");
+        assert!(style.foreground == Some(AnsiColor::Red));
+        assert!(style.background.is_none());
+    }
+
+    #[test]
+    fn ansi_spans_splits_on_style_changes() {
+        let spans: Vec<AnsiSpan> = ansi_spans("plain \u{1b}[1;31mred\u{1b}[0m done");
+        let texts: Vec<&str> = spans
+            .iter()
+            .map(|span: &AnsiSpan| span.text.as_str())
+            .collect();
+        assert_eq!(texts, vec!["plain ", "red", " done"]);
+        assert!(spans[0].style == AnsiStyle::default());
+        assert!(spans[1].style.bold);
+        assert!(spans[1].style.foreground == Some(AnsiColor::Red));
+        assert!(spans[2].style == AnsiStyle::default());
+    }
+
+    #[test]
+    fn ansi_spans_merges_codes_that_change_nothing() {
+        let spans: Vec<AnsiSpan> = ansi_spans("a\u{1b}[0mb\u{1b}[mc");
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].text, "abc");
+    }
+
+    #[test]
+    fn strip_ansi_drops_every_escape() {
+        assert_eq!(
+            strip_ansi(
+                "\u{1b}[2K\u{1b}[32mok\u{1b}[0m \u{1b}]0;title\u{7}\u{1b}]8;;url\u{1b}\\link\u{1b}7"
+            ),
+            "ok link"
+        );
+        assert_eq!(strip_ansi("no escapes"), "no escapes");
+        assert_eq!(strip_ansi("\u{1b}[31m"), "");
+    }
+
+    #[test]
+    fn parse_log_header_splits_airflow_format() {
+        let header = parse_log_header(
+            "[2025-05-18T12:00:00.000+0000] {taskinstance.py:1234} ERROR - Task failed",
+        );
+        let (timestamp, source, level, message) = header.expect("header");
+        assert_eq!(timestamp, "2025-05-18T12:00:00.000+0000");
+        assert_eq!(source, "taskinstance.py:1234");
+        assert!(level == Some(LogLevel::Error));
+        assert_eq!(message, "Task failed");
+    }
+
+    #[test]
+    fn parse_log_header_allows_empty_message() {
+        let (_, _, level, message) =
+            parse_log_header("[2025-05-18 12:00:00,000] {logging_mixin.py:12} INFO -")
+                .expect("header");
+        assert!(level == Some(LogLevel::Info));
+        assert_eq!(message, "");
+    }
+
+    #[test]
+    fn parse_log_header_keeps_unknown_levels() {
+        let (_, _, level, message) =
+            parse_log_header("[2025-05-18] {file.py:1} NOTICE - hello").expect("header");
+        assert!(level.is_none());
+        assert_eq!(message, "hello");
+    }
+
+    #[test]
+    fn parse_log_header_rejects_header_less_lines() {
+        assert!(parse_log_header("Traceback (most recent call last):").is_none());
+        assert!(parse_log_header("[2025-05-18] no source here").is_none());
+        assert!(parse_log_header("[2025-05-18] {file.py:1} INFO no dash").is_none());
+        assert!(parse_log_header("").is_none());
+    }
+
+    #[test]
+    fn parse_log_carries_level_to_header_less_lines() {
+        let lines: Vec<LogLine> = parse_log(concat!(
+            "started without a header\r\n",
+            "[2025-05-18] {file.py:1} ERROR - boom\r\n",
+            "Traceback (most recent call last):\r",
+            "[2025-05-18] {file.py:2} INFO - recovered\n",
+            "  still info\n",
+        ));
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines
+                .iter()
+                .map(|line: &LogLine| line.line_number)
+                .collect::<Vec<u64>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+        assert!(lines[0].level.is_none());
+        assert!(lines[0].timestamp.is_none());
+        assert!(lines[1].level == Some(LogLevel::Error));
+        assert!(lines[2].level == Some(LogLevel::Error));
+        assert!(lines[2].source.is_none());
+        assert_eq!(lines[2].message, "Traceback (most recent call last):");
+        assert!(lines[3].level == Some(LogLevel::Info));
+        assert!(lines[4].level == Some(LogLevel::Info));
+    }
+
+    #[test]
+    fn parse_log_parses_through_ansi_colors() {
+        let lines: Vec<LogLine> =
+            parse_log("\u{1b}[32m[2025-05-18] {file.py:1} WARNING - careful\u{1b}[0m");
+
+        assert!(lines[0].level == Some(LogLevel::Warning));
+        assert_eq!(lines[0].message, "careful");
+        assert!(lines[0].text.starts_with('\u{1b}'));
+    }
+
+    #[test]
+    fn kyubey_schema_check_accepts_newer_schemas() {
+        assert!(kyubey_schema_check(Some(3), Some(3)).is_ok());
+        assert!(kyubey_schema_check(Some(4), Some(3)).is_ok());
+        assert!(kyubey_schema_check(Some(2), Some(3)).is_err());
+        assert!(kyubey_schema_check(None, Some(3)).is_err());
     }
 }
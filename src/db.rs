@@ -1,7 +1,16 @@
-use crate::core::{DagRun, DagState, System, Task, TaskState};
-use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
-use sqlx::{Postgres, Transaction, query_as};
-use std::str::FromStr;
+use crate::core::{
+    ActiveRun, CalendarDay, DagRun, DagState, Failure, FailureStatus, FlakyTask, Note, NoteSource,
+    RunDuration, RunOutcome, SavedSearch, Sla, SlaBreach, System, Task, TaskState, UsualDuration,
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use sqlx::{
+    Connection, PgConnection, PgPool, Postgres, Transaction, migrate::Migrator, query, query_as,
+    query_scalar,
+};
+use std::{collections::HashMap, str::FromStr};
+
+/// Migrations for Kyubey's own schema, kept apart from the fake Airflow tables in `migrations/`
+static KYUBEY_MIGRATOR: Migrator = sqlx::migrate!("./kyubey_migrations");
 
 /// Results for a single system
 struct SystemRow {
@@ -64,6 +73,7 @@ impl DagRunRow {
             state,
             start_date: self.start_date,
             end_date: self.end_date,
+            sla_status: None,
         }
     }
 }
@@ -102,6 +112,256 @@ impl TaskRow {
     }
 }
 
+/// How long a finished Dag Run or Task took
+struct DurationRow {
+    dag_id: String,
+    task_id: Option<String>,
+    run_id: String,
+    execution_date: DateTime<Utc>,
+    duration: Option<f64>,
+}
+
+impl DurationRow {
+    /// Convert a DurationRow to a RunDuration
+    fn into_run_duration(self) -> Option<RunDuration> {
+        Some(RunDuration {
+            dag_id: self.dag_id,
+            task_id: self.task_id,
+            run_id: self.run_id,
+            execution_date: self.execution_date,
+            duration: self.duration?,
+        })
+    }
+}
+
+/// How many Dag Runs a System had on a day, and every state they ended up in
+struct CalendarDayRow {
+    day: Option<NaiveDate>,
+    runs: Option<i64>,
+    states: Option<Vec<String>>,
+}
+
+impl CalendarDayRow {
+    /// Convert a CalendarDayRow to a CalendarDay
+    fn into_calendar_day(self) -> Option<CalendarDay> {
+        let worst_state: Option<DagState> = self
+            .states
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|text: &String| DagState::from_str(text).ok())
+            .max_by_key(DagState::severity);
+
+        Some(CalendarDay {
+            date: self.day?,
+            runs: u64::try_from(self.runs?).ok()?,
+            worst_state,
+        })
+    }
+}
+
+/// How often a System's Task needed retries
+struct FlakyTaskRow {
+    dag_id: String,
+    task_id: String,
+    runs: Option<i64>,
+    retried: Option<i64>,
+    flaky_successes: Option<i64>,
+    exhausted: Option<i64>,
+    failed_attempts: Option<i64>,
+    mean_tries: Option<f64>,
+    retry_rate: Option<f64>,
+}
+
+impl FlakyTaskRow {
+    /// Convert a FlakyTaskRow to a FlakyTask
+    fn into_flaky_task(self) -> Option<FlakyTask> {
+        Some(FlakyTask {
+            dag_id: self.dag_id,
+            task_id: self.task_id,
+            runs: u64::try_from(self.runs?).ok()?,
+            retried: u64::try_from(self.retried?).ok()?,
+            flaky_successes: u64::try_from(self.flaky_successes?).ok()?,
+            exhausted: u64::try_from(self.exhausted?).ok()?,
+            failed_attempts: u64::try_from(self.failed_attempts?).ok()?,
+            mean_tries: self.mean_tries?,
+            retry_rate: self.retry_rate?,
+        })
+    }
+}
+
+/// A Dag Run or Task still in flight, and which System it belongs to
+struct ActiveRow {
+    client_name: Option<String>,
+    client_id: Option<String>,
+    system_name: Option<String>,
+    system_id: Option<String>,
+    dag_id: String,
+    run_id: String,
+    task_id: Option<String>,
+    state: Option<String>,
+    queued_at: Option<DateTime<Utc>>,
+    start_date: Option<DateTime<Utc>>,
+}
+
+impl ActiveRow {
+    /// Convert an ActiveRow to an ActiveRun
+    fn into_active_run(self) -> Option<ActiveRun> {
+        Some(ActiveRun {
+            client_name: self.client_name?,
+            client_id: self.client_id?,
+            system_name: self.system_name?,
+            system_id: self.system_id?,
+            dag_id: self.dag_id,
+            run_id: self.run_id,
+            task_id: self.task_id,
+            state: self.state?,
+            queued_at: self.queued_at,
+            start_date: self.start_date,
+        })
+    }
+}
+
+/// How long a Dag or Task usually takes
+struct UsualDurationRow {
+    dag_id: String,
+    task_id: Option<String>,
+    median: Option<f64>,
+}
+
+impl UsualDurationRow {
+    /// Convert a UsualDurationRow to a UsualDuration
+    fn into_usual_duration(self) -> Option<UsualDuration> {
+        Some(UsualDuration {
+            dag_id: self.dag_id,
+            task_id: self.task_id,
+            median: self.median?,
+        })
+    }
+}
+
+/// A note left on a System, Dag Run or Task attempt
+struct NoteRow {
+    author: String,
+    body: String,
+    created_at: DateTime<Utc>,
+}
+
+impl NoteRow {
+    /// Convert a NoteRow to a Note
+    fn into_note(self, source: NoteSource) -> Note {
+        Note {
+            source,
+            author: self.author,
+            body: self.body,
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// A failed Dag Run, and who is looking after it
+struct FailureRow {
+    client_name: Option<String>,
+    client_id: Option<String>,
+    system_name: Option<String>,
+    system_id: Option<String>,
+    dag_id: String,
+    run_id: String,
+    end_date: Option<DateTime<Utc>>,
+    assignee: Option<String>,
+    acknowledged_by: Option<String>,
+    acknowledged_at: Option<DateTime<Utc>>,
+    resolved_by: Option<String>,
+    resolved_at: Option<DateTime<Utc>>,
+}
+
+impl FailureRow {
+    /// Convert a FailureRow to a Failure
+    fn into_failure(self) -> Option<Failure> {
+        let status: FailureStatus = match (&self.acknowledged_at, &self.resolved_at) {
+            (_, Some(_)) => FailureStatus::Resolved,
+            (Some(_), None) => FailureStatus::Acknowledged,
+            (None, None) => FailureStatus::Unacknowledged,
+        };
+
+        Some(Failure {
+            client_name: self.client_name?,
+            client_id: self.client_id?,
+            system_name: self.system_name?,
+            system_id: self.system_id?,
+            dag_id: self.dag_id,
+            run_id: self.run_id,
+            failed_at: self.end_date,
+            status,
+            assignee: self.assignee,
+            acknowledged_by: self.acknowledged_by,
+            acknowledged_at: self.acknowledged_at,
+            resolved_by: self.resolved_by,
+            resolved_at: self.resolved_at,
+        })
+    }
+}
+
+/// How a Dag Run turned out, and which System it belongs to
+struct OutcomeRow {
+    client_name: Option<String>,
+    client_id: Option<String>,
+    system_name: Option<String>,
+    system_id: Option<String>,
+    dag_id: String,
+    execution_date: DateTime<Utc>,
+    end_date: Option<DateTime<Utc>>,
+    state: Option<String>,
+    tasks: Option<i64>,
+    retried_tasks: Option<i64>,
+}
+
+impl OutcomeRow {
+    /// Convert an OutcomeRow to a RunOutcome
+    fn into_run_outcome(self) -> Option<RunOutcome> {
+        let state: Option<DagState> = match self.state {
+            Some(text) => DagState::from_str(&text).ok(),
+            None => None,
+        };
+
+        Some(RunOutcome {
+            client_name: self.client_name?,
+            client_id: self.client_id?,
+            system_name: self.system_name?,
+            system_id: self.system_id?,
+            dag_id: self.dag_id,
+            execution_date: self.execution_date,
+            end_date: self.end_date,
+            state,
+            tasks: u64::try_from(self.tasks.unwrap_or(0)).ok()?,
+            retried_tasks: u64::try_from(self.retried_tasks.unwrap_or(0)).ok()?,
+        })
+    }
+}
+
+/// An SLA kept in Kyubey's own schema
+struct SlaRow {
+    system_id: String,
+    dag_id: Option<String>,
+    max_duration_minutes: Option<i32>,
+    deadline_minutes: Option<i32>,
+}
+
+impl SlaRow {
+    /// Convert an SlaRow to an Sla
+    fn into_sla(self) -> Option<Sla> {
+        let minutes = |minutes: Option<i32>| -> Option<Option<u32>> {
+            minutes.map(u32::try_from).transpose().ok()
+        };
+
+        Some(Sla {
+            system_id: self.system_id,
+            dag_id: self.dag_id,
+            max_duration_minutes: minutes(self.max_duration_minutes)?,
+            deadline_minutes: minutes(self.deadline_minutes)?,
+        })
+    }
+}
+
 /// Featch a single system
 pub async fn system_select(
     tx: &mut Transaction<'_, Postgres>,
@@ -409,3 +669,1375 @@ pub async fn task_select(
 
     Ok(task)
 }
+
+/// Pull how long each successful Dag Run of a System since a point in time took
+pub async fn dag_run_durations_by_system_select(
+    tx: &mut Transaction<'_, Postgres>,
+    system_id: &str,
+    since: &DateTime<Utc>,
+) -> Result<Vec<RunDuration>, sqlx::Error> {
+    // Pull all finished dag runs for a system in the window
+    let rows = query_as!(
+        DurationRow,
+        "SELECT
+            dag_run.dag_id,
+            NULL::text AS task_id,
+            dag_run.run_id,
+            dag_run.execution_date,
+            EXTRACT(EPOCH FROM (dag_run.end_date - dag_run.start_date))::float8 AS duration
+        FROM
+            dag_run
+        INNER JOIN
+            api_trigger
+        ON
+            dag_run.run_id = api_trigger.run_id
+        WHERE
+            api_trigger.details ->> 'system_id' = $1
+            AND api_trigger.details ->> 'client_name' IS NOT NULL
+            AND api_trigger.details ->> 'client_id' IS NOT NULL
+            AND api_trigger.details ->> 'system_name' IS NOT NULL
+            AND api_trigger.details ->> 'team_name' IS NOT NULL
+            AND api_trigger.details ->> 'team_id' IS NOT NULL
+            AND dag_run.state = 'success'
+            AND dag_run.start_date IS NOT NULL
+            AND dag_run.end_date IS NOT NULL
+            AND dag_run.execution_date >= $2
+        ORDER BY
+            dag_run.execution_date,
+            dag_run.dag_id",
+        system_id,
+        since,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Filter out rows we could not compute a duration for
+    let durations: Vec<RunDuration> = rows
+        .into_iter()
+        .filter_map(|row: DurationRow| row.into_run_duration())
+        .collect();
+
+    Ok(durations)
+}
+
+/// Pull how long each successful Task of a System since a point in time took
+pub async fn task_durations_by_system_select(
+    tx: &mut Transaction<'_, Postgres>,
+    system_id: &str,
+    since: &DateTime<Utc>,
+) -> Result<Vec<RunDuration>, sqlx::Error> {
+    // Pull all finished tasks for a system in the window
+    let rows = query_as!(
+        DurationRow,
+        "SELECT
+            task_instance.dag_id,
+            task_instance.task_id AS \"task_id?\",
+            task_instance.run_id,
+            dag_run.execution_date,
+            EXTRACT(EPOCH FROM (task_instance.end_date - task_instance.start_date))::float8
+                AS duration
+        FROM
+            task_instance
+        INNER JOIN
+            dag_run
+        ON
+            task_instance.dag_id = dag_run.dag_id
+            AND task_instance.run_id = dag_run.run_id
+        INNER JOIN
+            api_trigger
+        ON
+            dag_run.run_id = api_trigger.run_id
+        WHERE
+            api_trigger.details ->> 'system_id' = $1
+            AND api_trigger.details ->> 'client_name' IS NOT NULL
+            AND api_trigger.details ->> 'client_id' IS NOT NULL
+            AND api_trigger.details ->> 'system_name' IS NOT NULL
+            AND api_trigger.details ->> 'team_name' IS NOT NULL
+            AND api_trigger.details ->> 'team_id' IS NOT NULL
+            AND task_instance.state = 'success'
+            AND task_instance.start_date IS NOT NULL
+            AND task_instance.end_date IS NOT NULL
+            AND dag_run.execution_date >= $2
+        ORDER BY
+            dag_run.execution_date,
+            task_instance.dag_id,
+            task_instance.task_id",
+        system_id,
+        since,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Filter out rows we could not compute a duration for
+    let durations: Vec<RunDuration> = rows
+        .into_iter()
+        .filter_map(|row: DurationRow| row.into_run_duration())
+        .collect();
+
+    Ok(durations)
+}
+
+/// Pull how every Dag Run since a point in time turned out
+pub async fn dag_run_outcomes_select(
+    tx: &mut Transaction<'_, Postgres>,
+    since: &DateTime<Utc>,
+) -> Result<Vec<RunOutcome>, sqlx::Error> {
+    // Pull all dag runs in the window, with how many of their tasks needed a retry
+    let rows = query_as!(
+        OutcomeRow,
+        "SELECT
+            api_trigger.details ->> 'client_name' AS client_name,
+            api_trigger.details ->> 'client_id' AS client_id,
+            api_trigger.details ->> 'system_name' AS system_name,
+            api_trigger.details ->> 'system_id' AS system_id,
+            dag_run.dag_id,
+            dag_run.execution_date,
+            dag_run.end_date,
+            dag_run.state,
+            (
+                SELECT
+                    COUNT(*)
+                FROM
+                    task_instance
+                WHERE
+                    task_instance.dag_id = dag_run.dag_id
+                    AND task_instance.run_id = dag_run.run_id
+            ) AS tasks,
+            (
+                SELECT
+                    COUNT(*)
+                FROM
+                    task_instance
+                WHERE
+                    task_instance.dag_id = dag_run.dag_id
+                    AND task_instance.run_id = dag_run.run_id
+                    AND task_instance.try_number > 1
+            ) AS retried_tasks
+        FROM
+            dag_run
+        INNER JOIN
+            api_trigger
+        ON
+            dag_run.run_id = api_trigger.run_id
+        WHERE
+            dag_run.execution_date >= $1
+            AND api_trigger.details ->> 'client_name' IS NOT NULL
+            AND api_trigger.details ->> 'client_id' IS NOT NULL
+            AND api_trigger.details ->> 'system_name' IS NOT NULL
+            AND api_trigger.details ->> 'system_id' IS NOT NULL
+        ORDER BY
+            api_trigger.details ->> 'system_id',
+            dag_run.dag_id,
+            dag_run.execution_date",
+        since,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Filter out partial system rows. Only full details allowed
+    let outcomes: Vec<RunOutcome> = rows
+        .into_iter()
+        .filter_map(|row: OutcomeRow| row.into_run_outcome())
+        .collect();
+
+    Ok(outcomes)
+}
+
+/// Pull how many Dag Runs a System had per day, and the states they ended up in
+pub async fn dag_run_calendar_by_system_select(
+    tx: &mut Transaction<'_, Postgres>,
+    system_id: &str,
+    since: &DateTime<Utc>,
+) -> Result<Vec<CalendarDay>, sqlx::Error> {
+    // Roll up dag runs by the UTC day they were scheduled for
+    let rows = query_as!(
+        CalendarDayRow,
+        "SELECT
+            (dag_run.execution_date AT TIME ZONE 'UTC')::date AS day,
+            COUNT(*) AS runs,
+            ARRAY_AGG(DISTINCT dag_run.state) FILTER (WHERE dag_run.state IS NOT NULL) AS states
+        FROM
+            dag_run
+        INNER JOIN
+            api_trigger
+        ON
+            dag_run.run_id = api_trigger.run_id
+        WHERE
+            api_trigger.details ->> 'system_id' = $1
+            AND api_trigger.details ->> 'client_name' IS NOT NULL
+            AND api_trigger.details ->> 'client_id' IS NOT NULL
+            AND api_trigger.details ->> 'system_name' IS NOT NULL
+            AND api_trigger.details ->> 'team_name' IS NOT NULL
+            AND api_trigger.details ->> 'team_id' IS NOT NULL
+            AND dag_run.execution_date >= $2
+        GROUP BY
+            day
+        ORDER BY
+            day",
+        system_id,
+        since,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Drop any day we could not make sense of
+    let days: Vec<CalendarDay> = rows
+        .into_iter()
+        .filter_map(|row: CalendarDayRow| row.into_calendar_day())
+        .collect();
+
+    Ok(days)
+}
+
+/// Pull a System's Tasks that needed retries, flakiest first.
+/// Failed attempts are worked out from the try number, Airflows that keep `task_fail` can swap in
+/// their own record with `task_failures_by_system_select`.
+pub async fn flaky_tasks_by_system_select(
+    tx: &mut Transaction<'_, Postgres>,
+    system_id: &str,
+    since: &DateTime<Utc>,
+    min_runs: &i64,
+) -> Result<Vec<FlakyTask>, sqlx::Error> {
+    // Roll up every task instance, every try before the last failed, and so did the last if the
+    // task failed
+    let rows = query_as!(
+        FlakyTaskRow,
+        "SELECT
+            task_instance.dag_id,
+            task_instance.task_id,
+            COUNT(*) AS runs,
+            COUNT(*) FILTER (WHERE task_instance.try_number > 1) AS retried,
+            COUNT(*) FILTER (
+                WHERE task_instance.state = 'success' AND task_instance.try_number > 1
+            ) AS flaky_successes,
+            COUNT(*) FILTER (
+                WHERE task_instance.state = 'failed'
+                AND task_instance.try_number > task_instance.max_tries
+            ) AS exhausted,
+            COALESCE(SUM(
+                CASE
+                    WHEN task_instance.state = 'failed' THEN task_instance.try_number
+                    ELSE GREATEST(task_instance.try_number - 1, 0)
+                END
+            ), 0)::bigint AS failed_attempts,
+            AVG(GREATEST(task_instance.try_number, 1))::float8 AS mean_tries,
+            (COUNT(*) FILTER (WHERE task_instance.try_number > 1))::float8
+                / COUNT(*)::float8 AS retry_rate
+        FROM
+            task_instance
+        INNER JOIN
+            dag_run
+        ON
+            task_instance.dag_id = dag_run.dag_id
+            AND task_instance.run_id = dag_run.run_id
+        INNER JOIN
+            api_trigger
+        ON
+            dag_run.run_id = api_trigger.run_id
+        WHERE
+            api_trigger.details ->> 'system_id' = $1
+            AND api_trigger.details ->> 'client_name' IS NOT NULL
+            AND api_trigger.details ->> 'client_id' IS NOT NULL
+            AND api_trigger.details ->> 'system_name' IS NOT NULL
+            AND api_trigger.details ->> 'team_name' IS NOT NULL
+            AND api_trigger.details ->> 'team_id' IS NOT NULL
+            AND dag_run.execution_date >= $2
+        GROUP BY
+            task_instance.dag_id,
+            task_instance.task_id
+        HAVING
+            COUNT(*) >= $3
+            AND COUNT(*) FILTER (WHERE task_instance.try_number > 1) > 0
+        ORDER BY
+            retry_rate DESC,
+            failed_attempts DESC,
+            task_instance.dag_id,
+            task_instance.task_id",
+        system_id,
+        since,
+        min_runs,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Drop any task we could not make sense of
+    let tasks: Vec<FlakyTask> = rows
+        .into_iter()
+        .filter_map(|row: FlakyTaskRow| row.into_flaky_task())
+        .collect();
+
+    Ok(tasks)
+}
+
+/// Pull how many failed attempts Airflow recorded in `task_fail` for each Task of a System.
+/// Only for Airflows that still have the table, newer versions dropped it.
+pub async fn task_failures_by_system_select(
+    tx: &mut Transaction<'_, Postgres>,
+    system_id: &str,
+    since: &DateTime<Utc>,
+) -> Result<HashMap<(String, String), u64>, sqlx::Error> {
+    // Count failures for the same Dag Runs the flaky task roll up covers
+    let rows = query!(
+        "SELECT
+            task_fail.dag_id,
+            task_fail.task_id,
+            COUNT(*) AS failures
+        FROM
+            task_fail
+        INNER JOIN
+            dag_run
+        ON
+            task_fail.dag_id = dag_run.dag_id
+            AND task_fail.run_id = dag_run.run_id
+        INNER JOIN
+            api_trigger
+        ON
+            dag_run.run_id = api_trigger.run_id
+        WHERE
+            api_trigger.details ->> 'system_id' = $1
+            AND api_trigger.details ->> 'client_name' IS NOT NULL
+            AND api_trigger.details ->> 'client_id' IS NOT NULL
+            AND api_trigger.details ->> 'system_name' IS NOT NULL
+            AND api_trigger.details ->> 'team_name' IS NOT NULL
+            AND api_trigger.details ->> 'team_id' IS NOT NULL
+            AND dag_run.execution_date >= $2
+        GROUP BY
+            task_fail.dag_id,
+            task_fail.task_id",
+        system_id,
+        since,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Drop any count we could not make sense of
+    let failures: HashMap<(String, String), u64> = rows
+        .into_iter()
+        .filter_map(|row| {
+            Some((
+                (row.dag_id, row.task_id),
+                u64::try_from(row.failures?).ok()?,
+            ))
+        })
+        .collect();
+
+    Ok(failures)
+}
+
+/// Pull every Dag Run that is queued or running
+pub async fn active_dag_runs_select(
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<Vec<ActiveRun>, sqlx::Error> {
+    // Pull all dag runs still in flight
+    let rows = query_as!(
+        ActiveRow,
+        "SELECT
+            api_trigger.details ->> 'client_name' AS client_name,
+            api_trigger.details ->> 'client_id' AS client_id,
+            api_trigger.details ->> 'system_name' AS system_name,
+            api_trigger.details ->> 'system_id' AS system_id,
+            dag_run.dag_id,
+            dag_run.run_id,
+            NULL::varchar AS task_id,
+            dag_run.state,
+            dag_run.queued_at,
+            dag_run.start_date
+        FROM
+            dag_run
+        INNER JOIN
+            api_trigger
+        ON
+            dag_run.run_id = api_trigger.run_id
+        WHERE
+            dag_run.state IN ('queued', 'running')
+        ORDER BY
+            dag_run.execution_date",
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Filter out partial system rows. Only full details allowed
+    let active: Vec<ActiveRun> = rows
+        .into_iter()
+        .filter_map(|row: ActiveRow| row.into_active_run())
+        .collect();
+
+    Ok(active)
+}
+
+/// Pull every Task that is running
+pub async fn active_tasks_select(
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<Vec<ActiveRun>, sqlx::Error> {
+    // Pull all tasks still in flight
+    let rows = query_as!(
+        ActiveRow,
+        "SELECT
+            api_trigger.details ->> 'client_name' AS client_name,
+            api_trigger.details ->> 'client_id' AS client_id,
+            api_trigger.details ->> 'system_name' AS system_name,
+            api_trigger.details ->> 'system_id' AS system_id,
+            task_instance.dag_id,
+            task_instance.run_id,
+            task_instance.task_id AS \"task_id?\",
+            task_instance.state,
+            task_instance.queued_dttm AS queued_at,
+            task_instance.start_date
+        FROM
+            task_instance
+        INNER JOIN
+            api_trigger
+        ON
+            task_instance.run_id = api_trigger.run_id
+        WHERE
+            task_instance.state = 'running'
+        ORDER BY
+            task_instance.start_date",
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Filter out partial system rows. Only full details allowed
+    let active: Vec<ActiveRun> = rows
+        .into_iter()
+        .filter_map(|row: ActiveRow| row.into_active_run())
+        .collect();
+
+    Ok(active)
+}
+
+/// Pull the median duration of every Dag's successful runs since a point in time
+pub async fn usual_dag_run_durations_select(
+    tx: &mut Transaction<'_, Postgres>,
+    since: &DateTime<Utc>,
+    min_runs: &i64,
+) -> Result<Vec<UsualDuration>, sqlx::Error> {
+    // Median of every dag with enough history
+    let rows = query_as!(
+        UsualDurationRow,
+        "SELECT
+            dag_run.dag_id,
+            NULL::varchar AS task_id,
+            PERCENTILE_CONT(0.5) WITHIN GROUP (
+                ORDER BY EXTRACT(EPOCH FROM dag_run.end_date - dag_run.start_date)
+            )::float8 AS median
+        FROM
+            dag_run
+        WHERE
+            dag_run.state = 'success'
+            AND dag_run.start_date IS NOT NULL
+            AND dag_run.end_date IS NOT NULL
+            AND dag_run.execution_date >= $1
+        GROUP BY
+            dag_run.dag_id
+        HAVING
+            COUNT(*) >= $2",
+        since,
+        min_runs,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Drop any dag we could not make sense of
+    let durations: Vec<UsualDuration> = rows
+        .into_iter()
+        .filter_map(|row: UsualDurationRow| row.into_usual_duration())
+        .collect();
+
+    Ok(durations)
+}
+
+/// Pull the median duration of every Task's successful runs since a point in time
+pub async fn usual_task_durations_select(
+    tx: &mut Transaction<'_, Postgres>,
+    since: &DateTime<Utc>,
+    min_runs: &i64,
+) -> Result<Vec<UsualDuration>, sqlx::Error> {
+    // Median of every task with enough history
+    let rows = query_as!(
+        UsualDurationRow,
+        "SELECT
+            task_instance.dag_id,
+            task_instance.task_id AS \"task_id?\",
+            PERCENTILE_CONT(0.5) WITHIN GROUP (
+                ORDER BY EXTRACT(EPOCH FROM task_instance.end_date - task_instance.start_date)
+            )::float8 AS median
+        FROM
+            task_instance
+        INNER JOIN
+            dag_run
+        ON
+            task_instance.dag_id = dag_run.dag_id
+            AND task_instance.run_id = dag_run.run_id
+        WHERE
+            task_instance.state = 'success'
+            AND task_instance.start_date IS NOT NULL
+            AND task_instance.end_date IS NOT NULL
+            AND dag_run.execution_date >= $1
+        GROUP BY
+            task_instance.dag_id,
+            task_instance.task_id
+        HAVING
+            COUNT(*) >= $2",
+        since,
+        min_runs,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Drop any task we could not make sense of
+    let durations: Vec<UsualDuration> = rows
+        .into_iter()
+        .filter_map(|row: UsualDurationRow| row.into_usual_duration())
+        .collect();
+
+    Ok(durations)
+}
+
+/// Can Kyubey keep its own schema in this database, false for read-only databases and users
+pub async fn kyubey_schema_writable_select(pool: &PgPool) -> Result<bool, sqlx::Error> {
+    query_scalar!(
+        r#"SELECT
+            NOT pg_is_in_recovery()
+            AND current_setting('transaction_read_only') = 'off'
+            AND (
+                has_database_privilege(current_database(), 'CREATE')
+                OR EXISTS (
+                    SELECT
+                        1
+                    FROM
+                        pg_namespace
+                    WHERE
+                        pg_namespace.nspname = 'kyubey'
+                        AND has_schema_privilege(pg_namespace.oid, 'CREATE')
+                )
+            ) AS "writable!""#,
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Create Kyubey's own schema and bring it up to date
+pub async fn kyubey_schema_migrate(pool: &PgPool) -> Result<(), sqlx::Error> {
+    // Detached from the pool, so the search path below dies with this connection however we leave
+    let mut conn: PgConnection = pool.acquire().await?.detach();
+
+    // Track our migrations inside our own schema, so they never mix with Airflow's
+    query("CREATE SCHEMA IF NOT EXISTS kyubey")
+        .execute(&mut conn)
+        .await?;
+    query("SET search_path TO kyubey")
+        .execute(&mut conn)
+        .await?;
+    let migrated = KYUBEY_MIGRATOR.run(&mut conn).await;
+    let closed = conn.close().await;
+    migrated?;
+
+    closed
+}
+
+/// Pull every anomaly we already alerted on
+pub async fn alert_keys_select(
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<Vec<String>, sqlx::Error> {
+    query_scalar!(
+        "SELECT
+            alert.key
+        FROM
+            kyubey.alert",
+    )
+    .fetch_all(&mut **tx)
+    .await
+}
+
+/// Remember anomalies we alerted on
+pub async fn alert_keys_insert(
+    tx: &mut Transaction<'_, Postgres>,
+    keys: &[String],
+) -> Result<(), sqlx::Error> {
+    query!(
+        "INSERT INTO kyubey.alert (
+            key
+        )
+        SELECT
+            UNNEST($1::varchar[])
+        ON CONFLICT (key) DO NOTHING",
+        keys,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Forget anomalies that cleared up
+pub async fn alert_keys_delete_except(
+    tx: &mut Transaction<'_, Postgres>,
+    keys: &[String],
+) -> Result<(), sqlx::Error> {
+    query!(
+        "DELETE FROM
+            kyubey.alert
+        WHERE
+            NOT (alert.key = ANY($1::varchar[]))",
+        keys,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Does this Airflow have its own Dag Run and Task notes, added in Airflow 2.5
+pub async fn airflow_notes_exist_select(pool: &PgPool) -> Result<bool, sqlx::Error> {
+    query_scalar!(
+        r#"SELECT
+            to_regclass('dag_run_note') IS NOT NULL
+            AND to_regclass('task_instance_note') IS NOT NULL AS "exist!""#,
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Check if this Airflow still records failed attempts in `task_fail`, newer versions dropped it
+pub async fn airflow_task_fail_exists_select(pool: &PgPool) -> Result<bool, sqlx::Error> {
+    query_scalar!(r#"SELECT to_regclass('task_fail') IS NOT NULL AS "exist!""#,)
+        .fetch_one(pool)
+        .await
+}
+
+/// Check if a Kyubey server already set up its own schema in this database
+pub async fn kyubey_schema_exists_select(pool: &PgPool) -> Result<bool, sqlx::Error> {
+    query_scalar!(r#"SELECT to_regclass('kyubey._sqlx_migrations') IS NOT NULL AS "exist!""#,)
+        .fetch_one(pool)
+        .await
+}
+
+/// Pull the notes Kyubey users left on a System, Dag Run or Task attempt, oldest first.
+/// Dag Run and Task notes are found by Run ID alone, a System ID only narrows them down.
+pub async fn notes_select(
+    tx: &mut Transaction<'_, Postgres>,
+    system_id: &Option<String>,
+    run_id: &Option<String>,
+    task_id: &Option<String>,
+    attempt: &Option<i32>,
+) -> Result<Vec<Note>, sqlx::Error> {
+    let rows = query_as!(
+        NoteRow,
+        "SELECT
+            note.author,
+            note.body,
+            note.created_at
+        FROM
+            kyubey.note
+        WHERE
+            ($1::varchar IS NULL OR note.system_id = $1)
+            AND note.run_id IS NOT DISTINCT FROM $2
+            AND note.task_id IS NOT DISTINCT FROM $3
+            AND note.attempt IS NOT DISTINCT FROM $4
+        ORDER BY
+            note.created_at",
+        system_id.as_deref(),
+        run_id.as_deref(),
+        task_id.as_deref(),
+        *attempt,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let notes: Vec<Note> = rows
+        .into_iter()
+        .map(|row: NoteRow| row.into_note(NoteSource::Kyubey))
+        .collect();
+
+    Ok(notes)
+}
+
+/// Leave a note on a System, Dag Run or Task attempt
+pub async fn note_insert(
+    tx: &mut Transaction<'_, Postgres>,
+    system_id: &str,
+    run_id: &Option<String>,
+    task_id: &Option<String>,
+    attempt: &Option<i32>,
+    author: &str,
+    body: &str,
+) -> Result<Note, sqlx::Error> {
+    let row = query_as!(
+        NoteRow,
+        "INSERT INTO kyubey.note (
+            system_id,
+            run_id,
+            task_id,
+            attempt,
+            author,
+            body
+        ) VALUES (
+            $1,
+            $2,
+            $3,
+            $4,
+            $5,
+            $6
+        )
+        RETURNING
+            author,
+            body,
+            created_at",
+        system_id,
+        run_id.as_deref(),
+        task_id.as_deref(),
+        *attempt,
+        author,
+        body,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(row.into_note(NoteSource::Kyubey))
+}
+
+/// Pull Airflow's own note for a Dag Run
+pub async fn dag_run_airflow_notes_select(
+    tx: &mut Transaction<'_, Postgres>,
+    run_id: &str,
+) -> Result<Vec<Note>, sqlx::Error> {
+    let rows = query_as!(
+        NoteRow,
+        r#"SELECT
+            COALESCE('Airflow user ' || dag_run_note.user_id, 'Airflow') AS "author!",
+            dag_run_note.content AS "body!",
+            dag_run_note.updated_at AS created_at
+        FROM
+            dag_run_note
+        INNER JOIN
+            dag_run
+        ON
+            dag_run_note.dag_run_id = dag_run.id
+        WHERE
+            dag_run.run_id = $1
+            AND dag_run_note.content IS NOT NULL"#,
+        run_id,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let notes: Vec<Note> = rows
+        .into_iter()
+        .map(|row: NoteRow| row.into_note(NoteSource::Airflow))
+        .collect();
+
+    Ok(notes)
+}
+
+/// Pull Airflow's own notes for a Task
+pub async fn task_airflow_notes_select(
+    tx: &mut Transaction<'_, Postgres>,
+    run_id: &str,
+    task_id: &str,
+) -> Result<Vec<Note>, sqlx::Error> {
+    let rows = query_as!(
+        NoteRow,
+        r#"SELECT
+            COALESCE('Airflow user ' || task_instance_note.user_id, 'Airflow') AS "author!",
+            task_instance_note.content AS "body!",
+            task_instance_note.updated_at AS created_at
+        FROM
+            task_instance_note
+        WHERE
+            task_instance_note.run_id = $1
+            AND task_instance_note.task_id = $2
+            AND task_instance_note.content IS NOT NULL
+        ORDER BY
+            task_instance_note.map_index"#,
+        run_id,
+        task_id,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let notes: Vec<Note> = rows
+        .into_iter()
+        .map(|row: NoteRow| row.into_note(NoteSource::Airflow))
+        .collect();
+
+    Ok(notes)
+}
+
+/// Pull failed Dag Runs since a point in time with who is looking after them, by status and assignee
+pub async fn failures_select(
+    tx: &mut Transaction<'_, Postgres>,
+    since: &DateTime<Utc>,
+    status: &Option<FailureStatus>,
+    assignee: &Option<String>,
+) -> Result<Vec<Failure>, sqlx::Error> {
+    let status: Option<String> = status.map(|status: FailureStatus| status.to_string());
+
+    // Handling that happened before the Dag Run last failed was for an earlier failure
+    let rows = query_as!(
+        FailureRow,
+        r#"SELECT
+            failures.client_name AS "client_name?",
+            failures.client_id AS "client_id?",
+            failures.system_name AS "system_name?",
+            failures.system_id AS "system_id?",
+            failures.dag_id AS "dag_id!",
+            failures.run_id AS "run_id!",
+            failures.end_date AS "end_date?",
+            failures.assignee AS "assignee?",
+            failures.acknowledged_by AS "acknowledged_by?",
+            failures.acknowledged_at AS "acknowledged_at?",
+            failures.resolved_by AS "resolved_by?",
+            failures.resolved_at AS "resolved_at?"
+        FROM (
+            SELECT
+                api_trigger.details ->> 'client_name' AS client_name,
+                api_trigger.details ->> 'client_id' AS client_id,
+                api_trigger.details ->> 'system_name' AS system_name,
+                api_trigger.details ->> 'system_id' AS system_id,
+                dag_run.dag_id,
+                dag_run.run_id,
+                dag_run.execution_date,
+                dag_run.end_date,
+                failure.assignee,
+                CASE
+                    WHEN failure.acknowledged_at >= COALESCE(dag_run.end_date, '-infinity')
+                    THEN failure.acknowledged_by
+                END AS acknowledged_by,
+                CASE
+                    WHEN failure.acknowledged_at >= COALESCE(dag_run.end_date, '-infinity')
+                    THEN failure.acknowledged_at
+                END AS acknowledged_at,
+                CASE
+                    WHEN failure.resolved_at >= COALESCE(dag_run.end_date, '-infinity')
+                    THEN failure.resolved_by
+                END AS resolved_by,
+                CASE
+                    WHEN failure.resolved_at >= COALESCE(dag_run.end_date, '-infinity')
+                    THEN failure.resolved_at
+                END AS resolved_at
+            FROM
+                dag_run
+            INNER JOIN
+                api_trigger
+            ON
+                dag_run.run_id = api_trigger.run_id
+            LEFT JOIN
+                kyubey.failure
+            ON
+                dag_run.run_id = failure.run_id
+            WHERE
+                dag_run.state = 'failed'
+                AND dag_run.execution_date >= $1
+                AND ($3::varchar IS NULL OR failure.assignee = $3)
+        ) AS failures
+        WHERE
+            $2::varchar IS NULL
+            OR $2 = CASE
+                WHEN failures.resolved_at IS NOT NULL THEN 'resolved'
+                WHEN failures.acknowledged_at IS NOT NULL THEN 'acknowledged'
+                ELSE 'unacknowledged'
+            END
+        ORDER BY
+            failures.execution_date DESC"#,
+        since,
+        status,
+        assignee.as_deref(),
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Filter out partial system rows. Only full details allowed
+    let failures: Vec<Failure> = rows
+        .into_iter()
+        .filter_map(|row: FailureRow| row.into_failure())
+        .collect();
+
+    Ok(failures)
+}
+
+/// Pull a single failed Dag Run, with who is looking after it
+pub async fn failure_select(
+    tx: &mut Transaction<'_, Postgres>,
+    run_id: &str,
+) -> Result<Failure, sqlx::Error> {
+    let row = query_as!(
+        FailureRow,
+        r#"SELECT
+            api_trigger.details ->> 'client_name' AS client_name,
+            api_trigger.details ->> 'client_id' AS client_id,
+            api_trigger.details ->> 'system_name' AS system_name,
+            api_trigger.details ->> 'system_id' AS system_id,
+            dag_run.dag_id,
+            dag_run.run_id,
+            dag_run.end_date,
+            failure.assignee AS "assignee?",
+            CASE
+                WHEN failure.acknowledged_at >= COALESCE(dag_run.end_date, '-infinity')
+                THEN failure.acknowledged_by
+            END AS "acknowledged_by?",
+            CASE
+                WHEN failure.acknowledged_at >= COALESCE(dag_run.end_date, '-infinity')
+                THEN failure.acknowledged_at
+            END AS "acknowledged_at?",
+            CASE
+                WHEN failure.resolved_at >= COALESCE(dag_run.end_date, '-infinity')
+                THEN failure.resolved_by
+            END AS "resolved_by?",
+            CASE
+                WHEN failure.resolved_at >= COALESCE(dag_run.end_date, '-infinity')
+                THEN failure.resolved_at
+            END AS "resolved_at?"
+        FROM
+            dag_run
+        INNER JOIN
+            api_trigger
+        ON
+            dag_run.run_id = api_trigger.run_id
+        LEFT JOIN
+            kyubey.failure
+        ON
+            dag_run.run_id = failure.run_id
+        WHERE
+            dag_run.state = 'failed'
+            AND dag_run.run_id = $1"#,
+        run_id,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    // Filter out partial system rows. Only full details allowed
+    row.into_failure().ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Forget acknowledging or resolving a Dag Run that has failed again since
+pub async fn failure_stale_clear(
+    tx: &mut Transaction<'_, Postgres>,
+    run_id: &str,
+) -> Result<(), sqlx::Error> {
+    query!(
+        "UPDATE
+            kyubey.failure
+        SET
+            acknowledged_by = CASE
+                WHEN failure.acknowledged_at < dag_run.end_date THEN NULL
+                ELSE failure.acknowledged_by
+            END,
+            acknowledged_at = CASE
+                WHEN failure.acknowledged_at < dag_run.end_date THEN NULL
+                ELSE failure.acknowledged_at
+            END,
+            resolved_by = CASE
+                WHEN failure.resolved_at < dag_run.end_date THEN NULL
+                ELSE failure.resolved_by
+            END,
+            resolved_at = CASE
+                WHEN failure.resolved_at < dag_run.end_date THEN NULL
+                ELSE failure.resolved_at
+            END
+        FROM
+            dag_run
+        WHERE
+            failure.run_id = $1
+            AND dag_run.run_id = failure.run_id
+            AND (
+                failure.acknowledged_at < dag_run.end_date
+                OR failure.resolved_at < dag_run.end_date
+            )",
+        run_id,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Acknowledge a failed Dag Run, keeping whoever got to it first
+pub async fn failure_acknowledge_upsert(
+    tx: &mut Transaction<'_, Postgres>,
+    run_id: &str,
+    by: &str,
+) -> Result<(), sqlx::Error> {
+    query!(
+        "INSERT INTO kyubey.failure (
+            run_id,
+            acknowledged_by,
+            acknowledged_at
+        ) VALUES (
+            $1,
+            $2,
+            now()
+        )
+        ON CONFLICT (run_id) DO UPDATE SET
+            acknowledged_by = COALESCE(failure.acknowledged_by, EXCLUDED.acknowledged_by),
+            acknowledged_at = COALESCE(failure.acknowledged_at, EXCLUDED.acknowledged_at)",
+        run_id,
+        by,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Hand a failed Dag Run to someone
+pub async fn failure_assign_upsert(
+    tx: &mut Transaction<'_, Postgres>,
+    run_id: &str,
+    assignee: &str,
+) -> Result<(), sqlx::Error> {
+    query!(
+        "INSERT INTO kyubey.failure (
+            run_id,
+            assignee
+        ) VALUES (
+            $1,
+            $2
+        )
+        ON CONFLICT (run_id) DO UPDATE SET
+            assignee = EXCLUDED.assignee",
+        run_id,
+        assignee,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Resolve a failed Dag Run, which also acknowledges it if nobody had yet
+pub async fn failure_resolve_upsert(
+    tx: &mut Transaction<'_, Postgres>,
+    run_id: &str,
+    by: &str,
+) -> Result<(), sqlx::Error> {
+    query!(
+        "INSERT INTO kyubey.failure (
+            run_id,
+            acknowledged_by,
+            acknowledged_at,
+            resolved_by,
+            resolved_at
+        ) VALUES (
+            $1,
+            $2,
+            now(),
+            $2,
+            now()
+        )
+        ON CONFLICT (run_id) DO UPDATE SET
+            acknowledged_by = COALESCE(failure.acknowledged_by, EXCLUDED.acknowledged_by),
+            acknowledged_at = COALESCE(failure.acknowledged_at, EXCLUDED.acknowledged_at),
+            resolved_by = COALESCE(failure.resolved_by, EXCLUDED.resolved_by),
+            resolved_at = COALESCE(failure.resolved_at, EXCLUDED.resolved_at)",
+        run_id,
+        by,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Pull every SLA Dags are held to
+pub async fn slas_select(tx: &mut Transaction<'_, Postgres>) -> Result<Vec<Sla>, sqlx::Error> {
+    let rows = query_as!(
+        SlaRow,
+        "SELECT
+            sla.system_id,
+            sla.dag_id,
+            sla.max_duration_minutes,
+            sla.deadline_minutes
+        FROM
+            kyubey.sla
+        ORDER BY
+            sla.system_id,
+            sla.dag_id NULLS FIRST",
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // Drop any SLA with minutes we could not make sense of
+    let slas: Vec<Sla> = rows
+        .into_iter()
+        .filter_map(|row: SlaRow| row.into_sla())
+        .collect();
+
+    Ok(slas)
+}
+
+/// Swap every SLA for the ones given
+pub async fn slas_replace(
+    tx: &mut Transaction<'_, Postgres>,
+    slas: &[Sla],
+) -> Result<(), sqlx::Error> {
+    let minutes = |minutes: Option<u32>| minutes.map(|minutes: u32| minutes as i32);
+    let system_ids: Vec<String> = slas.iter().map(|sla: &Sla| sla.system_id.clone()).collect();
+    let dag_ids: Vec<Option<String>> = slas.iter().map(|sla: &Sla| sla.dag_id.clone()).collect();
+    let max_durations: Vec<Option<i32>> = slas
+        .iter()
+        .map(|sla: &Sla| minutes(sla.max_duration_minutes))
+        .collect();
+    let deadlines: Vec<Option<i32>> = slas
+        .iter()
+        .map(|sla: &Sla| minutes(sla.deadline_minutes))
+        .collect();
+
+    query!("DELETE FROM kyubey.sla").execute(&mut **tx).await?;
+    query!(
+        "INSERT INTO kyubey.sla (
+            system_id,
+            dag_id,
+            max_duration_minutes,
+            deadline_minutes
+        )
+        SELECT
+            *
+        FROM
+            UNNEST($1::varchar[], $2::varchar[], $3::integer[], $4::integer[])",
+        &system_ids,
+        &dag_ids as &[Option<String>],
+        &max_durations as &[Option<i32>],
+        &deadlines as &[Option<i32>],
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Pull the Dag Runs of some Systems executed since a point in time, oldest first
+pub async fn dag_runs_by_systems_select(
+    tx: &mut Transaction<'_, Postgres>,
+    system_ids: &[String],
+    since: &DateTime<Utc>,
+) -> Result<Vec<DagRun>, sqlx::Error> {
+    let rows = query_as!(
+        DagRunRow,
+        "SELECT
+            dag_run.dag_id,
+            dag_run.execution_date,
+            dag_run.run_id,
+            api_trigger.details ->> 'system_id' AS system_id,
+            dag_run.state,
+            dag_run.start_date,
+            dag_run.end_date
+        FROM
+            dag_run
+        INNER JOIN
+            api_trigger
+        ON
+            dag_run.run_id = api_trigger.run_id
+        WHERE
+            api_trigger.details ->> 'system_id' = ANY($1::varchar[])
+            AND dag_run.execution_date >= $2
+        ORDER BY
+            dag_run.execution_date,
+            dag_run.dag_id",
+        system_ids,
+        since,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let dag_runs: Vec<DagRun> = rows
+        .into_iter()
+        .map(|row: DagRunRow| row.into_dag_run())
+        .collect();
+
+    Ok(dag_runs)
+}
+
+/// Record SLA breaches, keeping the first record of each Dag Run
+pub async fn sla_breaches_insert(
+    tx: &mut Transaction<'_, Postgres>,
+    breaches: &[SlaBreach],
+) -> Result<u64, sqlx::Error> {
+    let run_ids: Vec<String> = breaches
+        .iter()
+        .map(|breach: &SlaBreach| breach.run_id.clone())
+        .collect();
+    let system_ids: Vec<String> = breaches
+        .iter()
+        .map(|breach: &SlaBreach| breach.system_id.clone())
+        .collect();
+    let dag_ids: Vec<String> = breaches
+        .iter()
+        .map(|breach: &SlaBreach| breach.dag_id.clone())
+        .collect();
+    let due_ats: Vec<DateTime<Utc>> = breaches
+        .iter()
+        .map(|breach: &SlaBreach| breach.due_at)
+        .collect();
+
+    let result = query!(
+        "INSERT INTO kyubey.sla_breach (
+            run_id,
+            system_id,
+            dag_id,
+            due_at
+        )
+        SELECT
+            *
+        FROM
+            UNNEST($1::varchar[], $2::varchar[], $3::varchar[], $4::timestamptz[])
+        ON CONFLICT (run_id) DO NOTHING",
+        &run_ids,
+        &system_ids,
+        &dag_ids,
+        &due_ats,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Pull the Dag Runs recorded as breaching a System's SLAs, newest first
+pub async fn sla_breaches_by_system_select(
+    tx: &mut Transaction<'_, Postgres>,
+    system_id: &str,
+) -> Result<Vec<DagRun>, sqlx::Error> {
+    let rows = query_as!(
+        DagRunRow,
+        r#"SELECT
+            dag_run.dag_id,
+            dag_run.execution_date,
+            dag_run.run_id,
+            sla_breach.system_id AS "system_id?",
+            dag_run.state,
+            dag_run.start_date,
+            dag_run.end_date
+        FROM
+            kyubey.sla_breach
+        INNER JOIN
+            dag_run
+        ON
+            sla_breach.run_id = dag_run.run_id
+        WHERE
+            sla_breach.system_id = $1
+        ORDER BY
+            dag_run.execution_date DESC,
+            dag_run.dag_id"#,
+        system_id,
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let dag_runs: Vec<DagRun> = rows
+        .into_iter()
+        .map(|row: DagRunRow| row.into_dag_run())
+        .collect();
+
+    Ok(dag_runs)
+}
+
+/// Pull every saved search, by name
+pub async fn saved_searches_select(
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<Vec<SavedSearch>, sqlx::Error> {
+    query_as!(
+        SavedSearch,
+        "SELECT
+            saved_search.name,
+            saved_search.search_by,
+            saved_search.created_at
+        FROM
+            kyubey.saved_search
+        ORDER BY
+            saved_search.name",
+    )
+    .fetch_all(&mut **tx)
+    .await
+}
+
+/// Save a search, replacing what a search of the same name looked for
+pub async fn saved_search_upsert(
+    tx: &mut Transaction<'_, Postgres>,
+    name: &str,
+    search_by: &str,
+) -> Result<SavedSearch, sqlx::Error> {
+    query_as!(
+        SavedSearch,
+        "INSERT INTO kyubey.saved_search (
+            name,
+            search_by
+        ) VALUES (
+            $1,
+            $2
+        )
+        ON CONFLICT (name) DO UPDATE SET
+            search_by = EXCLUDED.search_by
+        RETURNING
+            saved_search.name,
+            saved_search.search_by,
+            saved_search.created_at",
+        name,
+        search_by,
+    )
+    .fetch_one(&mut **tx)
+    .await
+}
+
+/// Forget a saved search, returning if there was one to forget
+pub async fn saved_search_delete(
+    tx: &mut Transaction<'_, Postgres>,
+    name: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = query!(
+        "DELETE FROM
+            kyubey.saved_search
+        WHERE
+            saved_search.name = $1",
+        name,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Round trip to the database, to see if it is there
+pub async fn ping_select(pool: &PgPool) -> Result<(), sqlx::Error> {
+    query_scalar!(r#"SELECT 1 AS "one!""#)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Which of the Airflow tables Kyubey reads from are missing
+pub async fn airflow_tables_missing_select(pool: &PgPool) -> Result<Vec<String>, sqlx::Error> {
+    query_scalar!(
+        r#"SELECT
+            name AS "name!"
+        FROM
+            unnest(ARRAY['api_trigger', 'dag_run', 'task_instance']) AS name
+        WHERE
+            to_regclass(name) IS NULL"#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Latest migration applied to Kyubey's own schema
+pub async fn kyubey_schema_version_select(pool: &PgPool) -> Result<Option<i64>, sqlx::Error> {
+    query_scalar!(
+        "SELECT
+            MAX(version)
+        FROM
+            kyubey._sqlx_migrations
+        WHERE
+            success",
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Latest migration this build of Kyubey knows about
+pub fn kyubey_schema_latest_version() -> Option<i64> {
+    KYUBEY_MIGRATOR
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+}
@@ -0,0 +1,87 @@
+use std::{fmt, io};
+
+/// What Kyubey was asked for, so a not found error can say what was missing
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Entity {
+    System,
+    DagRun,
+    Task,
+    FailedDagRun,
+    SavedSearch,
+}
+
+impl fmt::Display for Entity {
+    /// How to formate the Entity for HTML rendering
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: &str = match self {
+            Entity::System => "System",
+            Entity::DagRun => "Dag Run",
+            Entity::Task => "Task",
+            Entity::FailedDagRun => "Failed Dag Run",
+            Entity::SavedSearch => "Saved Search",
+        };
+        write!(formatter, "{}", text)
+    }
+}
+
+/// Everything that can go wrong reading from Airflow, its logs, or Kyubey's own schema
+#[derive(Debug)]
+pub enum KyubeyError {
+    /// What was asked for does not exist
+    NotFound { entity: Entity, id: String },
+    /// A Dag Run exists, but no System claims it
+    NoParentSystem { run_id: String },
+    /// A Task has not started, so it has no logs to show
+    NoLogs { id: String },
+    /// The request does not make sense, like a note without a body
+    InvalidInput(String),
+    /// The log for a Task attempt could not be read
+    LogUnavailable { id: String, source: io::Error },
+    /// A feature that is switched off, like writing to a read-only database
+    Unavailable(String),
+    /// The database failed us
+    Backend(sqlx::Error),
+}
+
+impl KyubeyError {
+    /// Shorthand for a not found error
+    pub fn not_found(entity: Entity, id: &str) -> Self {
+        KyubeyError::NotFound {
+            entity,
+            id: id.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for KyubeyError {
+    /// How to formate the KyubeyError for users
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KyubeyError::NotFound { entity, id } => write!(formatter, "No {} {}", entity, id),
+            KyubeyError::NoParentSystem { run_id } => {
+                write!(formatter, "Dag Run {} has no parent System", run_id)
+            }
+            KyubeyError::NoLogs { id } => {
+                write!(formatter, "Task {} has not run yet, so it has no logs", id)
+            }
+            KyubeyError::InvalidInput(message) | KyubeyError::Unavailable(message) => {
+                write!(formatter, "{}", message)
+            }
+            KyubeyError::LogUnavailable { id, source } => {
+                write!(formatter, "Log {} is unavailable: {}", id, source)
+            }
+            KyubeyError::Backend(err) => write!(formatter, "Database error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for KyubeyError {
+    /// The error underneath, if there is one
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KyubeyError::LogUnavailable { source, .. } => Some(source),
+            KyubeyError::Backend(err) => Some(err),
+            _ => None,
+        }
+    }
+}
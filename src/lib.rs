@@ -0,0 +1,31 @@
+pub mod core;
+pub mod db;
+pub mod error;
+pub mod settings;
+
+use core::Sla;
+use sqlx::PgPool;
+
+/// Name of the only Airflow environment, when Kyubey is not told about more than one
+pub const DEFAULT_ENVIRONMENT: &str = "default";
+
+/// Struct to put our Configs into
+#[derive(Clone)]
+pub struct Config {
+    pub environment: String,
+    pub database_url: String,
+    pub log_path: String,
+    pub alert_webhook_url: Option<String>,
+    pub slas: Vec<Sla>,
+    pub kyubey_schema: bool,
+    pub airflow_notes: bool,
+    pub airflow_task_fail: bool,
+    pub page_size: u32,
+}
+
+/// An Airflow deployment Kyubey watches, with its own database and logs
+#[derive(Clone)]
+pub struct Environment {
+    pub config: Config,
+    pub pool: PgPool,
+}
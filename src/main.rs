@@ -1,24 +1,41 @@
+mod alert;
 mod api;
-mod core;
-mod db;
+mod sla;
+mod tls;
 mod ui;
 
 use api::Api;
+use clap::Parser;
 use color_eyre::eyre;
+use kyubey::{
+    Config, Environment,
+    db::{
+        airflow_notes_exist_select, airflow_task_fail_exists_select, kyubey_schema_migrate,
+        kyubey_schema_writable_select, slas_replace,
+    },
+    settings::{Cli, EnvironmentSettings, Settings},
+};
 use poem::{
-    EndpointExt, Route, Server, endpoint::EmbeddedFilesEndpoint, listener::TcpListener,
-    middleware::Tracing,
+    EndpointExt, Route, Server,
+    endpoint::{EmbeddedFilesEndpoint, make_sync},
+    get,
+    http::StatusCode,
+    listener::{BoxListener, Listener, TcpListener},
+    middleware::{ForceHttps, RequestId, ReuseId, Tracing},
 };
 use poem_openapi::OpenApiService;
 use rust_embed::Embed;
-use sqlx::PgPool;
-
-/// Struct to put our Configs into
-#[derive(Clone)]
-struct Config {
-    database_url: String,
-    log_path: String,
-}
+use sqlx::{
+    PgPool,
+    postgres::{PgConnectOptions, PgPoolOptions},
+};
+use std::{io, str::FromStr, time::Duration};
+use tokio::{
+    signal,
+    sync::watch,
+    task::JoinSet,
+    time::{Instant, sleep_until},
+};
 
 /// Static files hosted via webserver
 #[derive(Embed)]
@@ -30,42 +47,300 @@ async fn main() -> Result<(), eyre::Error> {
     // Lets get pretty error reports
     color_eyre::install()?;
 
+    // Let .env fill in environment variables, then layer the settings file, env and flags
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+    let print_config: bool = cli.print_config;
+    let settings: Settings = Settings::load(cli.settings)?;
+    if print_config {
+        print!("{}", settings.to_toml()?);
+        return Ok(());
+    }
+
     // Enable Poem's logging
     tracing_subscriber::fmt::init();
 
-    // Generate our configs to share between threads
-    let config = Config {
-        database_url: dotenvy::var("DATABASE_URL")?,
-        log_path: dotenvy::var("LOG_PATH")?,
+    // Setup our OpenAPI Service, the spec lists every environment as its own server
+    let api_service = |settings: &Settings| -> OpenApiService<Api, ()> {
+        settings.environments.iter().fold(
+            OpenApiService::new(Api, "Kyubey", "0.1.0"),
+            |api_service, environment: &EnvironmentSettings| {
+                api_service.server(format!(
+                    "{}/api/{}",
+                    settings.public_url.trim_end_matches('/'),
+                    environment.name
+                ))
+            },
+        )
     };
+    let spec = api_service(&settings).spec_endpoint();
+    let swagger = api_service(&settings).swagger_ui();
+
+    // Connect to every Airflow we watch
+    let mut environments: Vec<Environment> = Vec::new();
+    for config in settings.configs()? {
+        environments.push(connect(&settings, config).await?);
+    }
+
+    // Alert on stuck and long running work, if someone is listening
+    let (stop_workers, workers_stopping) = watch::channel(false);
+    let mut workers: JoinSet<()> = JoinSet::new();
+    if let Some(webhook_url) = &settings.alert_webhook_url {
+        for environment in &environments {
+            workers.spawn(alert::alert_loop(
+                environment.pool.clone(),
+                webhook_url.clone(),
+                environment.config.kyubey_schema,
+                environment.config.environment.clone(),
+                workers_stopping.clone(),
+            ));
+        }
+    }
 
-    // Setup our OpenAPI Service
-    let api_service = OpenApiService::new(Api, "Kyubey", "0.1.0").server("http://0.0.0.0:3000/api");
-    let spec = api_service.spec_endpoint();
-    let swagger = api_service.swagger_ui();
+    // Keep a record of SLA breaches wherever we have somewhere to keep it
+    for environment in &environments {
+        if environment.config.kyubey_schema {
+            workers.spawn(sla::sla_loop(
+                environment.pool.clone(),
+                environment.config.clone(),
+                workers_stopping.clone(),
+            ));
+        }
+    }
 
-    // Connect to PostgreSQL
-    let pool = PgPool::connect(&config.database_url).await?;
+    // Each environment gets its own API and pages, reading from its own database and logs.
+    // The first also answers without a prefix, so links from before environments keep working.
+    let mut api_route = Route::new();
+    let mut ui_route = ui::route();
+    if let Some(environment) = environments.first() {
+        api_route = api_route.nest(
+            "/",
+            api_service(&settings)
+                .data(environment.config.clone())
+                .data(environment.pool.clone()),
+        );
+        ui_route = ui_route.nest(
+            "/",
+            ui::environment_route()
+                .data(environment.config.clone())
+                .data(environment.pool.clone()),
+        );
+    }
+    for environment in &environments {
+        let path: String = format!("/{}", environment.config.environment);
+        api_route = api_route.nest(
+            &path,
+            api_service(&settings)
+                .data(environment.config.clone())
+                .data(environment.pool.clone()),
+        );
+        ui_route = ui_route.nest(
+            &path,
+            ui::environment_route()
+                .data(environment.config.clone())
+                .data(environment.pool.clone()),
+        );
+    }
 
     // Route inbound traffic
     let app = Route::new()
         // Developer friendly locations
-        .nest("/api", api_service)
+        .nest("/api", api_route.catch_all_error(api::problem_details))
         .nest("/assets", EmbeddedFilesEndpoint::<Assets>::new())
         .at("/spec", spec)
         .nest("/swagger", swagger)
+        // Probes for whatever runs Kyubey
+        .at("/healthz", get(api::healthz))
+        .at("/readyz", get(api::readyz))
         // User UI
-        .nest("/", ui::route())
+        .nest("/", ui_route.around(ui::error_page))
         // Global context to be shared
-        .data(config)
-        .data(pool)
+        .data(environments.clone())
         // Utilites being added to our services
-        .with(Tracing);
+        .with(Tracing)
+        .with(RequestId::default().reuse_id(ReuseId::Use));
 
-    // Lets run our service
-    Server::new(TcpListener::bind("0.0.0.0:3000"))
-        .run(app)
-        .await?;
+    // Serve HTTPS when given a certificate, reloading it as it changes
+    let listener: BoxListener = match (&settings.tls_cert_path, &settings.tls_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let (tls_configs, tls_watcher) =
+                tls::tls_configs(cert_path, key_path, workers_stopping.clone()).await?;
+            workers.spawn(tls_watcher);
+            TcpListener::bind((settings.bind_address, settings.port))
+                .rustls(tls_configs)
+                .boxed()
+        }
+        _ => TcpListener::bind((settings.bind_address, settings.port)).boxed(),
+    };
+
+    // On a shutdown signal, background workers stop first so nothing new starts while we drain.
+    // Both share one deadline, so shutdown never takes longer than the configured timeout.
+    let shutdown_timeout = Duration::from_secs(settings.shutdown_timeout);
+    let (stop_servers, servers_stopping) = watch::channel::<Option<Instant>>(None);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        let deadline: Instant = Instant::now() + shutdown_timeout;
+        tracing::info!("Shutting down, stopping background workers");
+        stop_workers.send_replace(true);
+        // A worker stuck on a slow database or webhook is dropped, which aborts it
+        if tokio::time::timeout_at(deadline, workers.join_all())
+            .await
+            .is_err()
+        {
+            tracing::warn!("Background workers did not stop in time, abandoning them");
+        }
+
+        tracing::info!(
+            "Draining open connections for up to {}s",
+            deadline.saturating_duration_since(Instant::now()).as_secs()
+        );
+        stop_servers.send_replace(Some(deadline));
+    });
+
+    // Lets run our service, until we are asked to stop
+    let server = drain(
+        Server::new(listener).run_with_graceful_shutdown(
+            app,
+            stopping(servers_stopping.clone()),
+            None,
+        ),
+        servers_stopping.clone(),
+    );
+
+    // Send anyone using plain HTTP over to HTTPS
+    let redirect = async {
+        match settings.redirect_port {
+            Some(redirect_port) => {
+                let result = drain(
+                    Server::new(TcpListener::bind((settings.bind_address, redirect_port)))
+                        .run_with_graceful_shutdown(
+                            make_sync(|_| StatusCode::BAD_REQUEST)
+                                .with(ForceHttps::new().https_port(settings.port)),
+                            stopping(servers_stopping.clone()),
+                            None,
+                        ),
+                    servers_stopping.clone(),
+                )
+                .await;
+                // HTTPS keeps serving without it, so say so now rather than at shutdown
+                if let Err(err) = &result {
+                    tracing::error!("Redirect from port {redirect_port} stopped: {err}");
+                }
+                result
+            }
+            None => Ok(()),
+        }
+    };
+
+    // Let both finish draining before reporting either one failing
+    let (server, redirect) = tokio::join!(server, redirect);
+
+    // Hang up on every database once nothing is using them
+    for environment in &environments {
+        environment.pool.close().await;
+    }
+    server?;
+    redirect?;
+    tracing::info!("Shut down cleanly");
 
     Ok(())
 }
+
+/// Wait until servers are told to stop
+async fn stopping(mut servers_stopping: watch::Receiver<Option<Instant>>) {
+    servers_stopping.wait_for(Option::is_some).await.ok();
+}
+
+/// Run a server, giving up on connections still open once the shutdown deadline passes
+async fn drain(
+    server: impl Future<Output = io::Result<()>>,
+    mut servers_stopping: watch::Receiver<Option<Instant>>,
+) -> io::Result<()> {
+    let deadline = async {
+        match servers_stopping.wait_for(Option::is_some).await {
+            Ok(deadline) => {
+                let deadline: Option<Instant> = *deadline;
+                if let Some(deadline) = deadline {
+                    sleep_until(deadline).await;
+                }
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        result = server => result,
+        _ = deadline => {
+            tracing::warn!("Connections still open at the shutdown deadline, closing them");
+            Ok(())
+        }
+    }
+}
+
+/// Wait for Ctrl+C, or the SIGTERM sent by container runtimes like Kubernetes
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(err) = signal::ctrl_c().await {
+            tracing::error!("Unable to listen for Ctrl+C: {err}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                tracing::error!("Unable to listen for SIGTERM: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+/// Connect to an environment's database, and learn what it supports
+async fn connect(settings: &Settings, mut config: Config) -> Result<Environment, eyre::Error> {
+    // Connect to PostgreSQL, with the database cancelling queries that run away
+    let connect_options = PgConnectOptions::from_str(&config.database_url)?.options([(
+        "statement_timeout",
+        format!("{}s", settings.statement_timeout),
+    )]);
+    let pool: PgPool = PgPoolOptions::new()
+        .max_connections(settings.pool_size)
+        .acquire_timeout(Duration::from_secs(settings.database_timeout))
+        .connect_with(connect_options)
+        .await?;
+
+    // Keep what Kyubey produces in its own schema, unless the database is read-only
+    config.kyubey_schema = match settings.kyubey_schema {
+        Some(flag) => flag,
+        None => kyubey_schema_writable_select(&pool).await?,
+    };
+    if config.kyubey_schema {
+        kyubey_schema_migrate(&pool).await?;
+    }
+
+    // An SLA file replaces the SLAs kept in Kyubey's schema, which outlive it otherwise
+    if config.kyubey_schema && settings.sla_path.is_some() {
+        let mut tx = pool.begin().await?;
+        slas_replace(&mut tx, &config.slas).await?;
+        tx.commit().await?;
+    }
+
+    // Show Airflow's own notes, if this Airflow is new enough to have them
+    config.airflow_notes = airflow_notes_exist_select(&pool).await?;
+
+    // Count failed attempts from Airflow's own record, if this Airflow is old enough to keep one
+    config.airflow_task_fail = airflow_task_fail_exists_select(&pool).await?;
+
+    Ok(Environment { config, pool })
+}
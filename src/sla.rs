@@ -0,0 +1,43 @@
+use kyubey::{Config, core::sla_breaches_record, error::KyubeyError};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::time::Duration;
+use tokio::{sync::watch, time::interval};
+
+/// How often we look for new SLA breaches to record
+const SLA_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Record Dag Runs that breach their SLA in Kyubey's own schema, so breaches outlive SLA changes.
+/// Stops between checks once shutdown is signalled.
+pub async fn sla_loop(pool: PgPool, config: Config, mut shutdown: watch::Receiver<bool>) {
+    let mut ticker = interval(SLA_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.changed() => return,
+        }
+
+        match sla_once(&pool, &config).await {
+            Ok(0) => {}
+            Ok(recorded) => tracing::info!(
+                "Recorded {recorded} SLA breaches for {}",
+                config.environment
+            ),
+            Err(err) => tracing::error!(
+                "Unable to record SLA breaches for {}: {err}",
+                config.environment
+            ),
+        }
+    }
+}
+
+/// Record new SLA breaches once
+async fn sla_once(pool: &PgPool, config: &Config) -> Result<u64, KyubeyError> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(KyubeyError::Backend)?;
+
+    let recorded: u64 = sla_breaches_record(&mut tx, config).await?;
+    tx.commit().await.map_err(KyubeyError::Backend)?;
+
+    Ok(recorded)
+}
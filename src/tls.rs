@@ -0,0 +1,144 @@
+use color_eyre::eyre;
+use poem::listener::{RustlsCertificate, RustlsConfig};
+use rustls::{
+    crypto::aws_lc_rs::sign::any_supported_type,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    sign::CertifiedKey,
+};
+use std::time::{Duration, SystemTime};
+use tokio::{
+    fs,
+    sync::{mpsc, watch},
+    time::interval,
+};
+use tokio_stream::wrappers::ReceiverStream;
+
+/// How often we check if the certificate or key changed
+const TLS_RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Certificate and key as read from disk
+struct TlsFiles {
+    cert: Vec<u8>,
+    key: Vec<u8>,
+    modified: (SystemTime, SystemTime),
+}
+
+impl TlsFiles {
+    /// Read the certificate and key, failing if they do not make a usable pair
+    async fn read(cert_path: &str, key_path: &str) -> Result<TlsFiles, eyre::Error> {
+        let files = TlsFiles {
+            cert: fs::read(cert_path).await?,
+            key: fs::read(key_path).await?,
+            modified: modified(cert_path, key_path).await?,
+        };
+
+        // The key must belong to the certificate, so a half rotated pair never replaces a good one
+        files.certified_key()?.keys_match()?;
+
+        Ok(files)
+    }
+
+    /// Parse the certificate chain and key the way the listener will
+    fn certified_key(&self) -> Result<CertifiedKey, eyre::Error> {
+        let cert: Vec<CertificateDer<'static>> =
+            CertificateDer::pem_slice_iter(&self.cert).collect::<Result<_, _>>()?;
+        if cert.is_empty() {
+            eyre::bail!("No certificate found");
+        }
+        let key: PrivateKeyDer<'static> = PrivateKeyDer::from_pem_slice(&self.key)?;
+
+        Ok(CertifiedKey::new(cert, any_supported_type(&key)?))
+    }
+
+    /// Rustls config for the certificate and key
+    fn config(&self) -> RustlsConfig {
+        RustlsConfig::new().fallback(
+            RustlsCertificate::new()
+                .cert(self.cert.clone())
+                .key(self.key.clone()),
+        )
+    }
+}
+
+/// When the certificate and key were last changed
+async fn modified(
+    cert_path: &str,
+    key_path: &str,
+) -> Result<(SystemTime, SystemTime), eyre::Error> {
+    Ok((
+        fs::metadata(cert_path).await?.modified()?,
+        fs::metadata(key_path).await?.modified()?,
+    ))
+}
+
+/// Load the certificate and key, and a stream of TLS configs starting with them.
+/// The watcher feeds the stream each time the files change, and must be spawned to reload.
+pub async fn tls_configs(
+    cert_path: &str,
+    key_path: &str,
+    shutdown: watch::Receiver<bool>,
+) -> Result<
+    (
+        ReceiverStream<RustlsConfig>,
+        impl Future<Output = ()> + Send + 'static,
+    ),
+    eyre::Error,
+> {
+    // Fail on startup if what we were given will not work
+    let files: TlsFiles = TlsFiles::read(cert_path, key_path).await?;
+    let (sender, receiver) = mpsc::channel::<RustlsConfig>(1);
+    if sender.send(files.config()).await.is_err() {
+        eyre::bail!("Unable to hand the TLS certificate to the listener");
+    }
+
+    let watcher = watch_tls(
+        cert_path.to_string(),
+        key_path.to_string(),
+        files.modified,
+        sender,
+        shutdown,
+    );
+
+    Ok((ReceiverStream::new(receiver), watcher))
+}
+
+/// Reload the certificate and key when they change, keeping the last good pair on errors.
+/// Stops between checks once shutdown is signalled.
+async fn watch_tls(
+    cert_path: String,
+    key_path: String,
+    mut loaded: (SystemTime, SystemTime),
+    sender: mpsc::Sender<RustlsConfig>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut ticker = interval(TLS_RELOAD_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.changed() => return,
+        }
+
+        // Only reload once something was written
+        match modified(&cert_path, &key_path).await {
+            Ok(current) if current == loaded => continue,
+            Ok(_) => {}
+            Err(err) => {
+                tracing::error!("Unable to check {cert_path} and {key_path}: {err}");
+                continue;
+            }
+        }
+
+        match TlsFiles::read(&cert_path, &key_path).await {
+            Ok(files) => {
+                loaded = files.modified;
+                if sender.send(files.config()).await.is_err() {
+                    return;
+                }
+                tracing::info!("Reloaded TLS certificate from {cert_path}");
+            }
+            // Certificates are often rewritten one file at a time, so try again next tick
+            Err(err) => tracing::error!("Unable to reload TLS from {cert_path}: {err}"),
+        }
+    }
+}
@@ -1,42 +1,86 @@
 use crate::{
-    Config,
-    core::{System, Task, log_read, search_systems_read, task_read},
+    api::{database_error, http_error},
+    ui::{
+        snippet::{
+            dag_run_stats, log_attempt_tabs, log_compare_form, note_form, notes_list,
+            system_dag_runs_stat,
+        },
+        util::{
+            ansi_style_classes, dag_state_badge_type, failure_status_badge_type, fingerprint,
+            fingerprint_keys, format_duration, format_optional_rate, format_rate,
+            log_change_background_type, log_level_text_type, sla_status_badge_type,
+            success_rate_text_type, task_state_badge_type,
+        },
+    },
+};
+use chrono::NaiveDate;
+use kyubey::{
+    Config, Environment,
+    core::{
+        AnsiStyle, DagRun, DagRunTasks, DagScorecard, DagState, EnvironmentSearch,
+        EnvironmentSystem, EnvironmentWarning, Failure, FailureAction, FailureStatus,
+        FailureUpdate, LogDiff, LogLevel, LogLine, NewNote, NewSavedSearch, Note, NoteTarget,
+        Reliability, SavedSearch, ScorecardWindow, SystemDagRuns, SystemScorecard, Task, TaskState,
+        ansi_spans, dag_runs_for_system_read, failure_update, failures_read, log_diff_read,
+        log_lines_read, note_create, notes_read, require_kyubey_schema, saved_search_remove,
+        saved_search_save, saved_searches_read, scorecard_read, search_environments_read,
+        task_read, tasks_for_dag_run_read,
+    },
+    error::KyubeyError,
 };
 use maud::{Markup, html};
 use poem::{
-    error::InternalServerError,
-    handler,
+    IntoResponse, Response, handler,
     http::StatusCode,
-    web::{Data, Query},
+    web::{Data, Form, Path, Query},
 };
+use poem_openapi::types::ToJSON;
 use serde::Deserialize;
 use sqlx::{PgPool, Postgres, Transaction};
+use std::{cmp::Ordering, fmt};
 
 /// Data for System Search Web Component
 pub async fn search_systems_component(
-    tx: &mut Transaction<'_, Postgres>,
+    environments: &[Environment],
     search_by: &str,
     page: &u32,
 ) -> Result<Markup, poem::Error> {
-    // Search for anything that meets our criteria
-    let systems: Vec<System> = search_systems_read(tx, search_by, page).await?;
+    // Search every environment for anything that meets our criteria
+    let EnvironmentSearch {
+        systems,
+        warnings,
+        has_more,
+    } = search_environments_read(environments, search_by, page).await;
 
     // More Systems on next page?
-    let more_systems: Vec<System> = search_systems_read(tx, search_by, &(page + 1)).await?;
-
-    let next_page: Option<u32> = match more_systems.is_empty() {
-        true => None,
-        false => Some(page + 1),
+    let next_page: Option<u32> = match has_more {
+        true => Some(page + 1),
+        false => None,
     };
 
     Ok(html! {
+        // Environments we couldn't search, once at the top of the results
+        @if *page == 0 {
+            @for EnvironmentWarning { environment, message } in warnings {
+                tr id={ "warning_" (environment) } {
+                    td colspan="7" {
+                        div role="alert" class="alert alert-warning alert-soft" {
+                            span {
+                                "Couldn't search " span class="font-bold" { (environment) } ": " (message)
+                            }
+                        }
+                    }
+                }
+            }
+        }
         // One Row per System retuened
-        @for system in systems {
+        @for EnvironmentSystem { environment, system } in systems {
             tr
-                id={ "row_" (system.system_id) }
+                id={ "row_" (environment) "_" (system.system_id) }
                 class="hover:bg-base-300 cursor-pointer animate-fade-up"
-                href={ "/dag_runs/" (system.system_id) }
-                onclick={ "window.location='/dag_runs/" (system.system_id) "';" } {
+                href={ "/" (environment) "/dag_runs/" (system.system_id) }
+                onclick={ "window.location='/" (environment) "/dag_runs/" (system.system_id) "';" } {
+                td { span class="badge badge-outline badge-sm" { (environment) } }
                 td { (system.client_name) }
                 td { (system.client_id) }
                 td { (system.system_name) }
@@ -58,6 +102,812 @@ pub async fn search_systems_component(
     })
 }
 
+/// How often active Dag Runs and Tasks check for changes
+const REFRESH_TRIGGER: &str = "every 5s";
+
+/// Fingerprints of the stats and rows a browser is showing, so a refresh only sends what changed
+#[derive(Default, Deserialize)]
+pub struct Shown {
+    stats: Option<u64>,
+    layout: Option<u64>,
+    rows: Option<String>,
+}
+
+impl Shown {
+    /// Fingerprint the stats, which rows are listed, and each row
+    fn new(stats: &Markup, keys: &[&str], rows: &[Markup]) -> Self {
+        let rows: Vec<String> = rows
+            .iter()
+            .map(|row: &Markup| fingerprint(row).to_string())
+            .collect();
+
+        Shown {
+            stats: Some(fingerprint(stats)),
+            layout: Some(fingerprint_keys(keys)),
+            rows: Some(rows.join(".")),
+        }
+    }
+
+    /// Fingerprint of each row, in the order they are listed
+    fn row_fingerprints(&self) -> Vec<Option<u64>> {
+        self.rows
+            .as_deref()
+            .unwrap_or_default()
+            .split('.')
+            .map(|row: &str| row.parse::<u64>().ok())
+            .collect()
+    }
+
+    /// Query string telling the next refresh what is on screen
+    fn query(&self) -> String {
+        format!(
+            "stats={}&layout={}&rows={}",
+            self.stats.unwrap_or_default(),
+            self.layout.unwrap_or_default(),
+            self.rows.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// A table that keeps itself up to date while anything on it is active.
+/// Without what the browser is showing we render the table, otherwise only what changed
+/// is swapped in out of band. Rows line up by position, so adding or removing one swaps every row.
+/// The stats live elsewhere on the page, and are swapped by their own id.
+#[allow(clippy::too_many_arguments)]
+fn live_table<T>(
+    id: &str,
+    stats: impl Fn(Option<String>) -> Markup,
+    head: Markup,
+    items: &[T],
+    key: impl Fn(&T) -> &str,
+    row: impl Fn(&T, Option<String>) -> Markup,
+    refresh_url: Option<String>,
+    shown: &Option<Shown>,
+) -> Option<Markup> {
+    // What we would show now
+    let keys: Vec<&str> = items.iter().map(&key).collect();
+    let rows: Vec<Markup> = items.iter().map(|item: &T| row(item, None)).collect();
+    let current: Shown = Shown::new(&stats(None), &keys, &rows);
+
+    // Keep asking for changes, until nothing is active
+    let poller: Markup = html! {
+        div
+            id={ (id) "_refresh" }
+            hx-get=[refresh_url.as_ref().map(|url: &String| format!("{}{}", url, current.query()))]
+            hx-trigger=[refresh_url.as_ref().map(|_| REFRESH_TRIGGER)]
+            hx-swap="outerHTML" {}
+    };
+
+    let Some(shown) = shown else {
+        return Some(html! {
+            div id=(id) {
+                table class="table table-zebra table-sm" {
+                    thead { (head) }
+                    tbody id={ (id) "_rows" } {
+                        @for row in &rows { (row) }
+                    }
+                }
+                (poller)
+            }
+        });
+    };
+
+    // Work out what changed
+    let stats_changed: bool = shown.stats != current.stats;
+    let layout_changed: bool = shown.layout != current.layout;
+    let shown_rows: Vec<Option<u64>> = shown.row_fingerprints();
+    let changed_rows: Vec<usize> = match layout_changed {
+        true => Vec::new(),
+        false => rows
+            .iter()
+            .enumerate()
+            .filter(|(index, row)| {
+                shown_rows.get(*index).copied().flatten() != Some(fingerprint(row))
+            })
+            .map(|(index, _)| index)
+            .collect(),
+    };
+    if !stats_changed && !layout_changed && changed_rows.is_empty() {
+        return None;
+    }
+
+    Some(html! {
+        (poller)
+        @if stats_changed {
+            (stats(Some("true".to_string())))
+        }
+        @if layout_changed {
+            tbody id={ (id) "_rows" } hx-swap-oob="true" {
+                @for row in &rows { (row) }
+            }
+        }
+        @for index in changed_rows {
+            (row(&items[index], Some(format!("outerHTML:#{}_rows > tr:nth-child({})", id, index + 1))))
+        }
+    })
+}
+
+/// Data for a System's Dag Runs Web Component, None if nothing changed since what is shown
+pub async fn dag_runs_component(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    system_id: &str,
+    day: &Option<NaiveDate>,
+    shown: &Option<Shown>,
+) -> Result<Option<Markup>, poem::Error> {
+    // Search for anything that meets our criteria
+    let dag_runs: SystemDagRuns = dag_runs_for_system_read(tx, config, system_id)
+        .await
+        .map_err(http_error)?;
+
+    // Keep checking for changes while any run is active
+    let active: bool = dag_runs.dag_runs.iter().any(|dag_run: &DagRun| {
+        dag_run
+            .state
+            .as_ref()
+            .is_some_and(|state: &DagState| !state.is_terminal())
+    });
+    let refresh_url: Option<String> = active.then(|| match day {
+        Some(day) => format!("component/dag_runs/{}?day={}&", system_id, day),
+        None => format!("component/dag_runs/{}?", system_id),
+    });
+
+    // Only list the runs from the day picked on the calendar
+    let shown_runs: Vec<&DagRun> = dag_runs
+        .dag_runs
+        .iter()
+        .filter(|dag_run: &&DagRun| {
+            day.is_none_or(|day: NaiveDate| dag_run.execution_date.date_naive() == day)
+        })
+        .collect();
+
+    let head: Markup = html! {
+        tr {
+            th { "Dag ID" }
+            th { "Execution Date" }
+            th { "State" }
+            th { "SLA" }
+            th { "Run ID" }
+            th { "Start Date" }
+            th { "End Date" }
+        }
+    };
+
+    Ok(live_table(
+        "dag_runs",
+        |swap_oob: Option<String>| system_dag_runs_stat(&dag_runs.system, swap_oob),
+        head,
+        &shown_runs,
+        |dag_run: &&DagRun| dag_run.run_id.as_str(),
+        |dag_run: &&DagRun, swap_oob: Option<String>| dag_run_row(dag_run, swap_oob),
+        refresh_url,
+        shown,
+    ))
+}
+
+/// A row in the Dag Run table
+fn dag_run_row(dag_run: &DagRun, swap_oob: Option<String>) -> Markup {
+    html! {
+        tr
+            id={ "row_" (dag_run.run_id) }
+            class="hover:bg-base-300 cursor-pointer"
+            hx-swap-oob=[swap_oob]
+            onclick={ "window.location='tasks/" (dag_run.run_id) "';"} {
+            // Dag ID and Execution Date
+            td { (dag_run.dag_id) }
+            td { (dag_run.execution_date) }
+            // Dag State Badge
+            @if let Some(state) = &dag_run.state {
+                td class={ "badge " (dag_state_badge_type(state)) } { (state) }
+            } @else {
+                td {}
+            }
+            // SLA Status Badge
+            td {
+                @if let Some(sla_status) = &dag_run.sla_status {
+                    span class={ "badge " (sla_status_badge_type(sla_status)) } { (sla_status) }
+                }
+            }
+            td { (dag_run.run_id) }
+            // Start and End Dates
+            td { @if let Some(start_date) = dag_run.start_date { (start_date) } }
+            td { @if let Some(end_date) = dag_run.end_date { (end_date) } }
+        }
+    }
+}
+
+/// Data for a Dag Run's Tasks Web Component, None if nothing changed since what is shown
+pub async fn tasks_component(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    run_id: &str,
+    shown: &Option<Shown>,
+) -> Result<Option<Markup>, poem::Error> {
+    // Search for anything that meets our criteria
+    let tasks: DagRunTasks = tasks_for_dag_run_read(tx, config, run_id)
+        .await
+        .map_err(http_error)?;
+
+    // Keep checking for changes while the dag run or any task is active.
+    // Tasks without a state never ran, so they are done once the dag run is.
+    let active: bool = tasks
+        .dag_run
+        .state
+        .as_ref()
+        .is_some_and(|state: &DagState| !state.is_terminal())
+        || tasks.tasks.iter().any(|task: &Task| {
+            task.state
+                .as_ref()
+                .is_some_and(|state: &TaskState| !state.is_terminal())
+        });
+    let refresh_url: Option<String> = active.then(|| format!("component/tasks/{}?", run_id));
+
+    let head: Markup = html! {
+        tr {
+            th { "Task ID" }
+            th { "State" }
+            th { "Start Date" }
+            th { "End Date" }
+            th { "Attempts" }
+        }
+    };
+
+    Ok(live_table(
+        "tasks",
+        |swap_oob: Option<String>| dag_run_stats(&tasks.dag_run, swap_oob),
+        head,
+        &tasks.tasks,
+        |task: &Task| task.task_id.as_str(),
+        task_row,
+        refresh_url,
+        shown,
+    ))
+}
+
+/// A row in the Task table, linking to the logs once the task has run
+fn task_row(task: &Task, swap_oob: Option<String>) -> Markup {
+    // Pre-compute / formate some values
+    let try_number: u32 = task.try_number.unwrap_or(0);
+    let start_date: String = match task.start_date {
+        Some(start_date) => start_date.to_string(),
+        None => "".to_string(),
+    };
+    let end_date: String = match task.end_date {
+        Some(end_date) => end_date.to_string(),
+        None => "".to_string(),
+    };
+
+    html! {
+        tr
+            id={ "row_" (task.task_id) }
+            class=(if try_number > 0 { "hover:bg-base-300 cursor-pointer" } else { "hover:bg-base-300" })
+            hx-swap-oob=[swap_oob]
+            onclick=[(try_number > 0).then(|| format!("window.location='logs/{}/{}';", task.run_id, task.task_id))] {
+            td { (task.task_id) }
+            // Task State Badge
+            @if let Some(state) = &task.state {
+                td class={ "badge " (task_state_badge_type(state)) } { (state) }
+            } @else {
+                td {}
+            }
+            // Start and End Date
+            td { (start_date) }
+            td { (end_date) }
+            // Attepts
+            @if try_number > 0 {
+                td class="text-center" { (try_number) }
+            } @else {
+                td {}
+            }
+        }
+    }
+}
+
+/// Day the Dag Runs are filtered to
+#[derive(Deserialize)]
+struct DagRunsRefreshParams {
+    day: Option<NaiveDate>,
+}
+
+/// Web Component to refresh a System's Dag Runs
+#[handler]
+pub async fn dag_runs_get(
+    Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
+    Path(system_id): Path<String>,
+    Query(params): Query<DagRunsRefreshParams>,
+    Query(shown): Query<Shown>,
+) -> Result<Response, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Render what changed, or tell htmx to leave the page alone
+    match dag_runs_component(&mut tx, config, &system_id, &params.day, &Some(shown)).await? {
+        Some(dag_runs) => Ok(dag_runs.into_response()),
+        None => Ok(StatusCode::NO_CONTENT.into_response()),
+    }
+}
+
+/// Web Component to refresh a Dag Run's Tasks
+#[handler]
+pub async fn tasks_get(
+    Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
+    Path(run_id): Path<String>,
+    Query(shown): Query<Shown>,
+) -> Result<Response, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Render what changed, or tell htmx to leave the page alone
+    match tasks_component(&mut tx, config, &run_id, &Some(shown)).await? {
+        Some(tasks) => Ok(tasks.into_response()),
+        None => Ok(StatusCode::NO_CONTENT.into_response()),
+    }
+}
+
+/// Columns the Reliability Scorecard can be sorted by
+#[derive(Clone, Copy, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScorecardSort {
+    System,
+    Runs,
+    #[default]
+    SuccessRate,
+    FailureRate,
+    RecoveryTime,
+    RetryRate,
+}
+
+impl fmt::Display for ScorecardSort {
+    /// How to formate the ScorecardSort for query strings
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: &str = match self {
+            ScorecardSort::System => "system",
+            ScorecardSort::Runs => "runs",
+            ScorecardSort::SuccessRate => "success_rate",
+            ScorecardSort::FailureRate => "failure_rate",
+            ScorecardSort::RecoveryTime => "recovery_time",
+            ScorecardSort::RetryRate => "retry_rate",
+        };
+        write!(formatter, "{}", text)
+    }
+}
+
+/// Order two sets of reliability numbers by a column, names are handled by the caller
+fn compare_reliability(sort: &ScorecardSort, left: &Reliability, right: &Reliability) -> Ordering {
+    match sort {
+        ScorecardSort::System => Ordering::Equal,
+        ScorecardSort::Runs => left.runs.cmp(&right.runs),
+        ScorecardSort::SuccessRate => left
+            .success_rate
+            .unwrap_or(0.0)
+            .total_cmp(&right.success_rate.unwrap_or(0.0)),
+        ScorecardSort::FailureRate => left
+            .failure_rate
+            .unwrap_or(0.0)
+            .total_cmp(&right.failure_rate.unwrap_or(0.0)),
+        ScorecardSort::RecoveryTime => left
+            .mean_time_to_recovery
+            .unwrap_or(0.0)
+            .total_cmp(&right.mean_time_to_recovery.unwrap_or(0.0)),
+        ScorecardSort::RetryRate => left
+            .task_retry_rate
+            .unwrap_or(0.0)
+            .total_cmp(&right.task_retry_rate.unwrap_or(0.0)),
+    }
+}
+
+/// Is there nothing to rate in the column yet? These sort last whichever way we sort
+fn unrated(sort: &ScorecardSort, reliability: &Reliability) -> bool {
+    match sort {
+        ScorecardSort::SuccessRate => reliability.success_rate.is_none(),
+        ScorecardSort::FailureRate => reliability.failure_rate.is_none(),
+        ScorecardSort::RetryRate => reliability.task_retry_rate.is_none(),
+        ScorecardSort::System | ScorecardSort::Runs | ScorecardSort::RecoveryTime => false,
+    }
+}
+
+/// Windows a user can pick for the Reliability Scorecard
+const SCORECARD_WINDOWS: [ScorecardWindow; 4] = [
+    ScorecardWindow::Day,
+    ScorecardWindow::Week,
+    ScorecardWindow::Month,
+    ScorecardWindow::Quarter,
+];
+
+/// Web Component for the Reliability Scorecard of every System
+pub async fn scorecard_component(
+    tx: &mut Transaction<'_, Postgres>,
+    window: &ScorecardWindow,
+    sort: &ScorecardSort,
+    descending: &bool,
+) -> Result<Markup, poem::Error> {
+    // Score every System and Dag
+    let mut scorecards: Vec<SystemScorecard> =
+        scorecard_read(tx, window).await.map_err(http_error)?;
+
+    // Sort Systems, and Dags within each System
+    for scorecard in &mut scorecards {
+        scorecard.dags.sort_by(|left, right| {
+            compare_reliability(sort, &left.reliability, &right.reliability)
+                .then_with(|| left.dag_id.cmp(&right.dag_id))
+        });
+        if *descending {
+            scorecard.dags.reverse();
+        }
+        scorecard
+            .dags
+            .sort_by_key(|dag: &DagScorecard| unrated(sort, &dag.reliability));
+    }
+    scorecards.sort_by(|left, right| {
+        compare_reliability(sort, &left.reliability, &right.reliability)
+            .then_with(|| left.system_name.cmp(&right.system_name))
+    });
+    if *descending {
+        scorecards.reverse();
+    }
+    scorecards.sort_by_key(|scorecard: &SystemScorecard| unrated(sort, &scorecard.reliability));
+
+    // Sortable column headers, clicking the current column flips the order
+    let columns: [(&str, ScorecardSort); 6] = [
+        ("System", ScorecardSort::System),
+        ("Dag Runs", ScorecardSort::Runs),
+        ("Success Rate", ScorecardSort::SuccessRate),
+        ("Failure Rate", ScorecardSort::FailureRate),
+        ("Mean Time to Recovery", ScorecardSort::RecoveryTime),
+        ("Task Retry Rate", ScorecardSort::RetryRate),
+    ];
+
+    Ok(html! {
+        div id="scorecard" {
+            // Window Tabs
+            div role="tablist" class="tabs tabs-box ml-8 mr-8 mb-4" {
+                @for current_window in SCORECARD_WINDOWS {
+                    @if current_window == *window {
+                        a role="tab" class="tab tab-active" { "Last " (current_window) }
+                    } @else {
+                        a
+                            role="tab"
+                            class="tab"
+                            hx-get={ "component/scorecard?window=" (current_window) "&sort=" (sort) "&descending=" (descending) }
+                            hx-trigger="click"
+                            hx-swap="outerHTML"
+                            hx-target="#scorecard" {
+                            "Last " (current_window)
+                        }
+                    }
+                }
+            }
+            // Scorecard Table
+            table class="table table-sm" {
+                thead {
+                    tr {
+                        @for (label, column) in columns {
+                            @let next_descending: bool = column == *sort && !*descending;
+                            th
+                                class="cursor-pointer hover:bg-base-300"
+                                hx-get={ "component/scorecard?window=" (window) "&sort=" (column) "&descending=" (next_descending) }
+                                hx-trigger="click"
+                                hx-swap="outerHTML"
+                                hx-target="#scorecard" {
+                                (label)
+                                @if column == *sort {
+                                    @if *descending { " ▼" } @else { " ▲" }
+                                }
+                            }
+                        }
+                    }
+                }
+                tbody {
+                    @for scorecard in &scorecards {
+                        // System Row
+                        tr
+                            class="hover:bg-base-300 cursor-pointer font-bold bg-base-200"
+                            onclick={ "window.location='dag_runs/" (scorecard.system_id) "';" } {
+                            td { (scorecard.client_name) " / " (scorecard.system_name) }
+                            (reliability_cells(&scorecard.reliability))
+                        }
+                        // Dag Rows
+                        @for dag in &scorecard.dags {
+                            tr class="opacity-80" {
+                                td class="pl-8" { (dag.dag_id) }
+                                (reliability_cells(&dag.reliability))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Table cells for a set of reliability numbers
+fn reliability_cells(reliability: &Reliability) -> Markup {
+    html! {
+        td class="text-center" { (reliability.runs) }
+        @match reliability.success_rate {
+            Some(success_rate) => td class=(success_rate_text_type(&success_rate)) { (format_rate(&success_rate)) },
+            None => td class="opacity-60" { "—" },
+        }
+        td { (format_optional_rate(&reliability.failure_rate)) }
+        td { @if let Some(recovery) = reliability.mean_time_to_recovery { (format_duration(&recovery)) } }
+        td { (format_optional_rate(&reliability.task_retry_rate)) }
+    }
+}
+
+/// Window and order to show the Reliability Scorecard in
+#[derive(Deserialize)]
+pub struct ScorecardParams {
+    #[serde(default)]
+    pub window: ScorecardWindow,
+    #[serde(default)]
+    pub sort: ScorecardSort,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// Web Component to re-sort or re-window the Reliability Scorecard
+#[handler]
+pub async fn scorecard_get(
+    Data(pool): Data<&PgPool>,
+    Query(params): Query<ScorecardParams>,
+) -> Result<Markup, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Render component
+    scorecard_component(&mut tx, &params.window, &params.sort, &params.descending).await
+}
+
+/// Failure statuses a user can switch between
+const FAILURE_STATUSES: [FailureStatus; 3] = [
+    FailureStatus::Unacknowledged,
+    FailureStatus::Acknowledged,
+    FailureStatus::Resolved,
+];
+
+/// Web Component for the queue of failed Dag Runs, and who is looking after them
+pub async fn failures_component(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    status: &FailureStatus,
+    assignee: &Option<String>,
+) -> Result<Markup, poem::Error> {
+    // Failures are tracked in Kyubey's own schema
+    if let Err(err) = require_kyubey_schema(config, "Tracking failures") {
+        return Ok(html! {
+            p class="text-center opacity-60 animate-fade" { (err) }
+        });
+    }
+
+    // Everything in this status, so we know who it could be assigned to
+    let failures: Vec<Failure> = failures_read(tx, config, &Some(*status), &None)
+        .await
+        .map_err(http_error)?;
+    let mut assignees: Vec<&str> = failures
+        .iter()
+        .filter_map(|failure: &Failure| failure.assignee.as_deref())
+        .chain(assignee.as_deref())
+        .collect();
+    assignees.sort();
+    assignees.dedup();
+
+    // Only show what is assigned to who was picked
+    let shown: Vec<&Failure> = failures
+        .iter()
+        .filter(|failure: &&Failure| {
+            assignee.is_none() || failure.assignee.as_deref() == assignee.as_deref()
+        })
+        .collect();
+
+    Ok(html! {
+        div id="failures" {
+            div class="flex gap-4 items-center ml-8 mr-8 mb-4" {
+                // Status Tabs
+                div role="tablist" class="tabs tabs-box" {
+                    @for current_status in FAILURE_STATUSES {
+                        @if current_status == *status {
+                            a role="tab" class="tab tab-active capitalize" { (current_status) }
+                        } @else {
+                            a
+                                role="tab"
+                                class="tab capitalize"
+                                hx-get={ "component/failures?status=" (current_status) }
+                                hx-include="#failure_assignee"
+                                hx-trigger="click"
+                                hx-swap="outerHTML"
+                                hx-target="#failures" {
+                                (current_status)
+                            }
+                        }
+                    }
+                }
+                // Assignee Filter
+                select
+                    id="failure_assignee"
+                    class="select select-sm w-48"
+                    name="assignee"
+                    hx-get={ "component/failures?status=" (status) }
+                    hx-trigger="change"
+                    hx-swap="outerHTML"
+                    hx-target="#failures" {
+                    option value="" { "Assigned to anyone" }
+                    @for name in &assignees {
+                        option value=(name) selected[assignee.as_deref() == Some(*name)] { (name) }
+                    }
+                }
+            }
+            @if shown.is_empty() {
+                p class="text-center opacity-60 animate-fade" { "No " (status) " failures" }
+            } @else {
+                table class="table table-zebra table-sm animate-fade" {
+                    thead {
+                        tr {
+                            th { "Status" }
+                            th { "System" }
+                            th { "Dag ID" }
+                            th { "Run ID" }
+                            th { "Failed At" }
+                            th { "Assignee" }
+                            th { "Acknowledged" }
+                            th { "Resolved" }
+                            th { "Actions" }
+                        }
+                    }
+                    tbody {
+                        @for failure in shown {
+                            tr {
+                                td { span class={ "badge " (failure_status_badge_type(&failure.status)) } { (failure.status) } }
+                                td {
+                                    a class="link" href={ "dag_runs/" (failure.system_id) } {
+                                        (failure.client_name) " / " (failure.system_name)
+                                    }
+                                }
+                                td { (failure.dag_id) }
+                                td { a class="link" href={ "tasks/" (failure.run_id) } { (failure.run_id) } }
+                                td { @if let Some(failed_at) = failure.failed_at { (failed_at) } }
+                                td { @if let Some(assignee) = &failure.assignee { (assignee) } }
+                                td {
+                                    @if let (Some(by), Some(at)) = (&failure.acknowledged_by, failure.acknowledged_at) {
+                                        (by) div class="text-xs opacity-50" { (at) }
+                                    }
+                                }
+                                td {
+                                    @if let (Some(by), Some(at)) = (&failure.resolved_by, failure.resolved_at) {
+                                        (by) div class="text-xs opacity-50" { (at) }
+                                    }
+                                }
+                                td {
+                                    @if failure.status != FailureStatus::Resolved {
+                                        (failure_actions(failure, status))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Buttons to acknowledge, assign and resolve a failure from the queue
+fn failure_actions(failure: &Failure, status: &FailureStatus) -> Markup {
+    let action_url = |action: FailureAction| -> String {
+        format!(
+            "component/failures/{}/{}?status={}",
+            failure.run_id, action, status
+        )
+    };
+
+    html! {
+        div class="flex gap-2" {
+            @if failure.status == FailureStatus::Unacknowledged {
+                button
+                    class="btn btn-xs btn-warning"
+                    hx-post=(action_url(FailureAction::Acknowledge))
+                    hx-include="#failure_by, #failure_assignee"
+                    hx-swap="outerHTML"
+                    hx-target="#failures" {
+                    "Acknowledge"
+                }
+            }
+            button
+                class="btn btn-xs btn-success"
+                hx-post=(action_url(FailureAction::Resolve))
+                hx-include="#failure_by, #failure_assignee"
+                hx-swap="outerHTML"
+                hx-target="#failures" {
+                "Resolve"
+            }
+            form
+                class="join"
+                hx-post=(action_url(FailureAction::Assign))
+                hx-include="#failure_by, #failure_assignee"
+                hx-swap="outerHTML"
+                hx-target="#failures" {
+                input class="input input-xs join-item w-28" type="text" name="assign_to" placeholder="Assign to" required;
+                button class="btn btn-xs join-item" type="submit" { "Assign" }
+            }
+        }
+    }
+}
+
+/// Status and assignee to show failures for
+#[derive(Deserialize)]
+pub struct FailuresParams {
+    #[serde(default)]
+    pub status: FailureStatus,
+    pub assignee: Option<String>,
+}
+
+impl FailuresParams {
+    /// Picking "anyone" from the assignee filter sends an empty assignee
+    pub fn assignee(&self) -> Option<String> {
+        self.assignee
+            .clone()
+            .filter(|assignee: &String| !assignee.is_empty())
+    }
+}
+
+/// Web Component to switch the status or assignee of the failure queue
+#[handler]
+pub async fn failures_get(
+    Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
+    Query(params): Query<FailuresParams>,
+) -> Result<Markup, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Render component
+    failures_component(&mut tx, config, &params.status, &params.assignee()).await
+}
+
+/// Someone acting on a failure from the queue, along with the assignee filter to re-render with
+#[derive(Deserialize)]
+struct FailureForm {
+    by: String,
+    assign_to: Option<String>,
+    assignee: Option<String>,
+}
+
+/// Web Component to acknowledge, assign or resolve a failure, and show the queue again
+#[handler]
+pub async fn failure_post(
+    Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
+    Path((run_id, action)): Path<(String, FailureAction)>,
+    Query(params): Query<FailuresParams>,
+    Form(form): Form<FailureForm>,
+) -> Result<Markup, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Look after the failure
+    let update = FailureUpdate {
+        by: form.by,
+        assignee: form.assign_to,
+    };
+    failure_update(&mut tx, config, &run_id, &action, &update)
+        .await
+        .map_err(http_error)?;
+
+    // Render component
+    let params = FailuresParams {
+        assignee: form.assignee,
+        ..params
+    };
+    let failures: Markup =
+        failures_component(&mut tx, config, &params.status, &params.assignee()).await?;
+
+    // Commit Transaction
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(failures)
+}
+
 /// Paramiters to search by
 #[derive(Deserialize)]
 struct SearchParams {
@@ -68,68 +918,272 @@ struct SearchParams {
 /// Web Component to search for your system
 #[handler]
 pub async fn search_systems_get(
-    Data(pool): Data<&PgPool>,
+    Data(environments): Data<&Vec<Environment>>,
     Query(params): Query<SearchParams>,
 ) -> Result<Markup, poem::Error> {
-    // Start Transaction
-    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(InternalServerError)?;
-
     // Render component
-    search_systems_component(&mut tx, &params.search_by, &params.page).await
+    search_systems_component(environments, &params.search_by, &params.page).await
 }
 
+/// Log levels a user can filter the logs down to
+const LOG_LEVEL_FILTERS: [(&str, Option<LogLevel>); 3] = [
+    ("All", None),
+    ("Warning+", Some(LogLevel::Warning)),
+    ("Error", Some(LogLevel::Error)),
+];
+
 /// Web Component for showing logs
+#[allow(clippy::too_many_arguments)]
 pub async fn log_component(
+    tx: &mut Transaction<'_, Postgres>,
     config: &Config,
     dag_id: &str,
     run_id: &str,
     task_id: &str,
     attempt: &u32,
     try_number: &u32,
+    min_level: &Option<LogLevel>,
 ) -> Result<Markup, poem::Error> {
-    // Log for a task attempt
-    let log: String = log_read(config, dag_id, run_id, task_id, attempt).await?;
+    // Log for a task attempt, parsed and filtered by level
+    let lines: Vec<LogLine> = log_lines_read(config, dag_id, run_id, task_id, attempt, min_level)
+        .await
+        .map_err(http_error)?;
 
-    // Normalized all new lines to expected new lines
-    let log = log.replace("\r\n", "\n").replace('\r', "\n");
+    // Notes left on this attempt
+    let notes: Markup =
+        notes_component(tx, config, &NoteTarget::task(run_id, task_id, attempt)).await?;
+
+    // Link back to this component, minus the attempt and level
+    let log_url: String = format!(
+        "component/log?dag_id={}&run_id={}&task_id={}",
+        dag_id, run_id, task_id,
+    );
+    let level_param: String = match min_level {
+        Some(min_level) => format!("&min_level={}", min_level),
+        None => "".to_string(),
+    };
 
     Ok(html! {
         div id="logs" class="pl-4 pr-4" {
-            // Tabs
-            div role="tablist" class="tabs tabs-box" {
-                @for current_try in 1..=*try_number {
-                    // Highlight the current attempt tab
-                    @if current_try == *attempt {
-                        a
-                            role="tab"
-                            class="tab tab-active" {
-                            "Attempt: " (current_try)
-                        }
-                    // Have all other attemps link to their logs
-                    } @else {
-                        a
-                            role="tab"
-                            class="tab"
-                            hx-get={ "/component/log?dag_id=" (dag_id) "&run_id=" (run_id) "&task_id=" (task_id) "&attempt=" (current_try)  }
-                            hx-trigger="click"
-                            hx-swap="outerHTML"
-                            hx-target="#logs" {
-                            "Attempt: " (current_try)
+            div class="flex justify-between" {
+                // Tabs
+                (log_attempt_tabs(dag_id, run_id, task_id, &Some(*attempt), try_number, &level_param))
+                // Compare Attempts
+                @if *try_number > 1 {
+                    @let left_attempt: u32 = if *attempt > 1 { attempt - 1 } else { 1 };
+                    @let right_attempt: u32 = if *attempt > 1 { *attempt } else { 2 };
+                    (log_compare_form(dag_id, run_id, task_id, &left_attempt, &right_attempt, try_number))
+                }
+                // Log Level Filters
+                div role="tablist" class="tabs tabs-box" {
+                    @for (label, level) in LOG_LEVEL_FILTERS {
+                        @if level == *min_level {
+                            a
+                                role="tab"
+                                class="tab tab-active" {
+                                (label)
+                            }
+                        } @else {
+                            a
+                                role="tab"
+                                class="tab"
+                                hx-get={
+                                    (log_url) "&attempt=" (attempt)
+                                    @if let Some(level) = level { "&min_level=" (level) }
+                                }
+                                hx-push-url={
+                                    "logs/" (run_id) "/" (task_id) "?attempt=" (attempt)
+                                    @if let Some(level) = level { "&min_level=" (level) }
+                                }
+                                hx-trigger="click"
+                                hx-swap="outerHTML"
+                                hx-target="#logs" {
+                                (label)
+                            }
                         }
                     }
                 }
             }
+            (notes)
             // Show the logs
             div class="mockup-code w-full animate-fade" {
-                // Need to keep pre and code in the same line to avoid adding empty lines in teh logs
-                @for (index, line) in log.lines().enumerate() {
-                    pre data-prefix=(index + 1) { code { (line) } }
+                // Keep whitespace out of pre and code to avoid adding empty lines in teh logs
+                @for line in lines {
+                    @let color: &str = match &line.level {
+                        Some(level) => log_level_text_type(level),
+                        None => "",
+                    };
+                    pre id={ "L" (line.line_number) } data-prefix=(line.line_number) class=(color) {
+                        code {
+                            // Colored output becomes styled spans, the text itself is always escaped
+                            @for span in ansi_spans(&line.text) {
+                                @if span.style == AnsiStyle::default() {
+                                    (span.text)
+                                } @else {
+                                    span class=(ansi_style_classes(&span.style)) { (span.text) }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Web Component for the notes left on a System, Dag Run or Task attempt
+pub async fn notes_component(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+    target: &NoteTarget,
+) -> Result<Markup, poem::Error> {
+    // Notes from Kyubey and Airflow
+    let notes: Vec<Note> = notes_read(tx, config, target).await.map_err(http_error)?;
+
+    // Nothing to show, and nowhere to keep new notes
+    if notes.is_empty() && !config.kyubey_schema {
+        return Ok(html! {});
+    }
+
+    Ok(html! {
+        div id="notes" class="ml-8 mr-8 mb-4" {
+            div class="divider" { "Notes" }
+            (notes_list(&notes))
+            @if config.kyubey_schema {
+                (note_form(target))
+            }
+        }
+    })
+}
+
+/// Web Component to leave a note, and show it with the others
+#[handler]
+pub async fn notes_post(
+    Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
+    Form(new_note): Form<NewNote>,
+) -> Result<Markup, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Leave the note
+    note_create(&mut tx, config, &new_note)
+        .await
+        .map_err(http_error)?;
+
+    // Render component
+    let notes: Markup = notes_component(&mut tx, config, &new_note.target()).await?;
+
+    // Commit Transaction
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(notes)
+}
+
+/// Saved searches, each a button that runs it again, and a form to save the current search
+pub async fn saved_searches_component(
+    tx: &mut Transaction<'_, Postgres>,
+    config: &Config,
+) -> Result<Markup, poem::Error> {
+    let saved_searches: Vec<SavedSearch> =
+        saved_searches_read(tx, config).await.map_err(http_error)?;
+    let base: String = format!("/{}/component/saved_searches", config.environment);
+
+    Ok(html! {
+        div id="saved_searches" class="flex flex-wrap items-center gap-2 mt-2" {
+            span class="fieldset-label" { "Saved searches:" }
+            @for saved_search in &saved_searches {
+                @let search_by: String = saved_search.search_by.to_json_string();
+                div class="join" {
+                    button
+                        class="btn btn-xs btn-outline join-item"
+                        title=(saved_search.search_by)
+                        data-search-by=(saved_search.search_by)
+                        hx-get="/component/search_systems"
+                        hx-vals={ r#"{"search_by":"# (search_by) r#","page":0}"# }
+                        hx-target="#search_results"
+                        hx-swap="innerHTML"
+                        hx-on:click="document.getElementById('search_by_input').value = this.dataset.searchBy" {
+                        (saved_search.name)
+                    }
+                    button
+                        class="btn btn-xs btn-outline join-item"
+                        aria-label={ "Forget " (saved_search.name) }
+                        hx-post={ (base) "/delete" }
+                        hx-vals={ r#"{"name":"# (saved_search.name.to_json_string()) "}" }
+                        hx-target="#saved_searches"
+                        hx-swap="outerHTML" {
+                        "✕"
+                    }
                 }
             }
+            form
+                class="join"
+                hx-post=(base)
+                hx-include="#search_by_input"
+                hx-target="#saved_searches"
+                hx-swap="outerHTML" {
+                input class="input input-xs join-item w-40" type="text" name="name" placeholder="Name this search" required;
+                button class="btn btn-xs btn-primary join-item" type="submit" { "Save Search" }
+            }
         }
     })
 }
 
+/// Web Component to save the current search, and show it with the others
+#[handler]
+pub async fn saved_searches_post(
+    Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
+    Form(new_saved_search): Form<NewSavedSearch>,
+) -> Result<Markup, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Save the search
+    saved_search_save(&mut tx, config, &new_saved_search)
+        .await
+        .map_err(http_error)?;
+
+    // Render component
+    let saved_searches: Markup = saved_searches_component(&mut tx, config).await?;
+
+    // Commit Transaction
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(saved_searches)
+}
+
+/// Which saved search to forget
+#[derive(Deserialize)]
+struct SavedSearchParams {
+    name: String,
+}
+
+/// Web Component to forget a saved search, and show the rest
+#[handler]
+pub async fn saved_search_delete_post(
+    Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
+    Form(params): Form<SavedSearchParams>,
+) -> Result<Markup, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Forget the search
+    saved_search_remove(&mut tx, config, &params.name)
+        .await
+        .map_err(http_error)?;
+
+    // Render component
+    let saved_searches: Markup = saved_searches_component(&mut tx, config).await?;
+
+    // Commit Transaction
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(saved_searches)
+}
+
 /// Paramiters to Pull a log
 #[derive(Deserialize)]
 struct LogParams {
@@ -137,6 +1191,7 @@ struct LogParams {
     run_id: String,
     task_id: String,
     attempt: u32,
+    min_level: Option<LogLevel>,
 }
 
 /// Web Component to search for your system
@@ -147,28 +1202,148 @@ pub async fn log_get(
     Query(params): Query<LogParams>,
 ) -> Result<Markup, poem::Error> {
     // Start Transaction
-    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(InternalServerError)?;
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
 
     // Pull task details so we know how mmany runs their should be.
-    let task: Task = task_read(&mut tx, &params.run_id, &params.task_id).await?;
+    let task: Task = task_read(&mut tx, &params.run_id, &params.task_id)
+        .await
+        .map_err(http_error)?;
 
     // Make sure we should have logs from a run
     let try_number: u32 = match task.try_number {
         Some(try_number) if try_number > 0 => Ok(try_number),
-        _ => Err(poem::Error::from_string(
-            "No logs yet",
-            StatusCode::NOT_FOUND,
-        )),
-    }?;
+        _ => Err(KyubeyError::NoLogs {
+            id: format!("{}/{}", params.run_id, params.task_id),
+        }),
+    }
+    .map_err(http_error)?;
 
     // Render component
     log_component(
+        &mut tx,
         config,
         &params.dag_id,
         &params.run_id,
         &params.task_id,
         &params.attempt,
         &try_number,
+        &params.min_level,
+    )
+    .await
+}
+
+/// Web Component for comparing the logs of two attempts side by side
+pub async fn log_diff_component(
+    config: &Config,
+    dag_id: &str,
+    run_id: &str,
+    task_id: &str,
+    left_attempt: &u32,
+    right_attempt: &u32,
+    try_number: &u32,
+) -> Result<Markup, poem::Error> {
+    // Line up both attempts
+    let diff: LogDiff = log_diff_read(
+        config,
+        dag_id,
+        run_id,
+        task_id,
+        left_attempt,
+        right_attempt,
+        try_number,
+    )
+    .await
+    .map_err(http_error)?;
+
+    Ok(html! {
+        div id="logs" class="pl-4 pr-4" {
+            div class="flex justify-between" {
+                // Tabs, none active while comparing
+                (log_attempt_tabs(dag_id, run_id, task_id, &None, try_number, ""))
+                // Compare Attempts
+                (log_compare_form(dag_id, run_id, task_id, left_attempt, right_attempt, try_number))
+            }
+            // Show the diff
+            div class="overflow-x-auto animate-fade" {
+                table class="table table-xs font-mono" {
+                    thead {
+                        tr {
+                            th {}
+                            th { "Attempt: " (diff.left_attempt) }
+                            th {}
+                            th { "Attempt: " (diff.right_attempt) }
+                        }
+                    }
+                    tbody {
+                        @for row in diff.rows {
+                            @let (left_color, right_color) = log_change_background_type(&row.change);
+                            tr {
+                                @if let Some(left) = row.left {
+                                    td class={ "text-right opacity-60 " (left_color) } { (left.line_number) }
+                                    td class={ "whitespace-pre " (left_color) } { (left.text) }
+                                } @else {
+                                    td {}
+                                    td {}
+                                }
+                                @if let Some(right) = row.right {
+                                    td class={ "text-right opacity-60 " (right_color) } { (right.line_number) }
+                                    td class={ "whitespace-pre " (right_color) } { (right.text) }
+                                } @else {
+                                    td {}
+                                    td {}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Paramiters to compare two logs
+#[derive(Deserialize)]
+struct LogDiffParams {
+    dag_id: String,
+    run_id: String,
+    task_id: String,
+    left_attempt: u32,
+    right_attempt: u32,
+}
+
+/// Web Component to compare two attempts of a task
+#[handler]
+pub async fn log_diff_get(
+    Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
+    Query(params): Query<LogDiffParams>,
+) -> Result<Markup, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Pull task details so we know how mmany runs their should be.
+    let task: Task = task_read(&mut tx, &params.run_id, &params.task_id)
+        .await
+        .map_err(http_error)?;
+
+    // Make sure we should have logs from a run
+    let try_number: u32 = match task.try_number {
+        Some(try_number) if try_number > 0 => Ok(try_number),
+        _ => Err(KyubeyError::NoLogs {
+            id: format!("{}/{}", params.run_id, params.task_id),
+        }),
+    }
+    .map_err(http_error)?;
+
+    // Render component
+    log_diff_component(
+        config,
+        &params.dag_id,
+        &params.run_id,
+        &params.task_id,
+        &params.left_attempt,
+        &params.right_attempt,
+        &try_number,
     )
     .await
 }
@@ -1,9 +1,10 @@
 use crate::ui::snippet::{head, header};
 use maud::{DOCTYPE, Markup, html};
 
-/// Base Page Layout
+/// Base Page Layout, pages within an environment link relative to it
 pub fn base_layout(
     title: &str,
+    environment: &Option<String>,
     system_id: &Option<String>,
     run_id: &Option<String>,
     task_id: &Option<String>,
@@ -12,9 +13,9 @@ pub fn base_layout(
     html! {
         (DOCTYPE)
         html lang="en-US" {
-            (head())
+            (head(environment))
             body {
-                (header(title, system_id, run_id, task_id))
+                (header(title, environment, system_id, run_id, task_id))
                 main {
                     (main)
                 }
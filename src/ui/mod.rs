@@ -4,17 +4,44 @@ mod page;
 mod snippet;
 mod util;
 
-use component::{log_get, search_systems_get};
-use page::{dag_runs, index, logs, tasks};
-use poem::{Route, get};
+use component::{
+    dag_runs_get, failure_post, failures_get, log_diff_get, log_get, notes_post,
+    saved_search_delete_post, saved_searches_post, scorecard_get, search_systems_get, tasks_get,
+};
+pub use page::error_page;
+use page::{analytics, attention, dag_runs, failures, index, logs, scorecard, tasks};
+use poem::{Route, get, post};
 
-/// Router for UI
+/// Router for UI that covers every environment
 pub fn route() -> Route {
     Route::new()
         .at("/", get(index))
-        .at("/component/log", get(log_get))
         .at("/component/search_systems", get(search_systems_get))
+}
+
+/// Router for UI within one environment, nested under the environment's name.
+/// Search covers every environment, so an environment's root shows it too.
+pub fn environment_route() -> Route {
+    Route::new()
+        .at("/", get(index))
+        .at("/analytics/:system_id", get(analytics))
+        .at("/attention", get(attention))
+        .at("/component/dag_runs/:system_id", get(dag_runs_get))
+        .at("/component/failures", get(failures_get))
+        .at("/component/failures/:run_id/:action", post(failure_post))
+        .at("/component/log", get(log_get))
+        .at("/component/log_diff", get(log_diff_get))
+        .at("/component/notes", post(notes_post))
+        .at("/component/saved_searches", post(saved_searches_post))
+        .at(
+            "/component/saved_searches/delete",
+            post(saved_search_delete_post),
+        )
+        .at("/component/scorecard", get(scorecard_get))
+        .at("/component/tasks/:run_id", get(tasks_get))
         .at("/dag_runs/:sysetem_id", get(dag_runs))
+        .at("/failures", get(failures))
         .at("/logs/:run_id/:task_id", get(logs))
+        .at("/scorecard", get(scorecard))
         .at("/tasks/:run_id", get(tasks))
 }
@@ -1,39 +1,196 @@
 use crate::{
-    Config,
-    core::{
-        DagRun, DagRunTasks, System, SystemDagRuns, Task, dag_run_read, dag_runs_for_system_read,
-        system_for_dag_run_read, system_read, task_read, tasks_for_dag_run_read,
-    },
+    api::{database_error, http_error},
     ui::{
-        component::{log_component, search_systems_component},
+        component::{
+            FailuresParams, ScorecardParams, dag_runs_component, failures_component, log_component,
+            notes_component, saved_searches_component, scorecard_component,
+            search_systems_component, tasks_component,
+        },
         layout::base_layout,
-        snippet::{dag_run_stats, system_stats, task_stats},
-        util::{dag_state_badge_type, task_state_badge_type},
+        snippet::{
+            calendar_heatmap, dag_run_stats, log_line_script, sparkline, system_stats, task_stats,
+        },
+        util::{
+            anomaly_badge_type, dag_state_badge_type, format_duration, format_rate,
+            success_rate_text_type,
+        },
+    },
+};
+use chrono::NaiveDate;
+use kyubey::{
+    Config, Environment,
+    core::{
+        Anomaly, CalendarDay, DagRun, DurationPoint, LogLevel, NoteTarget, System, SystemDurations,
+        SystemFlakyTasks, SystemSlas, Task, attention_read, dag_run_read, system_calendar_read,
+        system_duration_trend_read, system_durations_read, system_flaky_tasks_read,
+        system_for_dag_run_read, system_read, system_slas_read, task_read,
     },
+    error::{Entity, KyubeyError},
 };
 use maud::{Markup, html};
 use poem::{
-    error::InternalServerError,
-    handler,
+    Endpoint, IntoResponse, Request, Response, handler,
     http::StatusCode,
-    web::{Data, Path},
+    middleware::ReqId,
+    web::{Data, Path, Query},
 };
+use serde::Deserialize;
 use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::Arc;
+
+/// Wrap the UI so any error becomes a webpage, keeping the layout so users can find their way back
+pub async fn error_page<E: Endpoint>(
+    endpoint: Arc<E>,
+    request: Request,
+) -> Result<Response, poem::Error> {
+    // Remember what was asked for before the request is handed off
+    let path: String = request.uri().path().to_string();
+    let request_id: Option<String> = request.data::<ReqId>().map(ReqId::to_string);
+    let environment: Option<String> = path
+        .split('/')
+        .nth(1)
+        .filter(|name: &&str| {
+            request
+                .data::<Vec<Environment>>()
+                .is_some_and(|environments: &Vec<Environment>| {
+                    environments
+                        .iter()
+                        .any(|environment: &Environment| environment.config.environment == *name)
+                })
+        })
+        .map(str::to_string);
+
+    let err: poem::Error = match endpoint.call(request).await {
+        Ok(response) => return Ok(response.into_response()),
+        Err(err) => err,
+    };
+    let status: StatusCode = err.status();
+    let title: &str = status.canonical_reason().unwrap_or("Error");
+
+    // Say what went wrong, without leaking internals when the fault is ours
+    let message: String = match err.downcast_ref::<KyubeyError>() {
+        _ if status.is_server_error() => {
+            tracing::error!("Unable to render {path}: {err}");
+            "Something went wrong on our side. If it keeps happening, reach out to support with the request ID below.".to_string()
+        }
+        Some(kyubey_err) => kyubey_err.to_string(),
+        None if status == StatusCode::NOT_FOUND => format!("There is no page at {path}"),
+        None => err.to_string(),
+    };
+
+    // Point users somewhere useful
+    let mut suggestions: Vec<(String, String)> = error_suggestions(&err, &path);
+    if environment.is_none() {
+        suggestions.retain(|(href, _): &(String, String)| href.starts_with('/'));
+    }
+
+    Ok(base_layout(
+        title,
+        &environment,
+        &None,
+        &None,
+        &None,
+        html! {
+            div class="flex justify-center mt-8 animate-fade" {
+                div class="w-1/2" {
+                    div role="alert" class="alert alert-error alert-soft" {
+                        div {
+                            h3 class="font-bold" { (status.as_u16()) " " (title) }
+                            p { (message) }
+                        }
+                    }
+                    ul class="list mt-4" {
+                        @for (href, text) in &suggestions {
+                            li class="list-row" {
+                                a class="link link-primary" href=(href) { (text) }
+                            }
+                        }
+                    }
+                    @if let Some(request_id) = &request_id {
+                        p class="text-sm opacity-60 mt-4" {
+                            "Request ID: " code { (request_id) }
+                        }
+                    }
+                }
+            }
+        },
+    )
+    .with_status(status)
+    .into_response())
+}
+
+/// Links that might help after an error, most specific first.
+/// Links without a leading slash stay within the environment the error happened in.
+fn error_suggestions(err: &poem::Error, path: &str) -> Vec<(String, String)> {
+    let mut suggestions: Vec<(String, String)> = Vec::new();
+
+    match err.downcast_ref::<KyubeyError>() {
+        // Task IDs look like run_id/task_id, maybe with an attempt on the end
+        Some(KyubeyError::NotFound {
+            entity: Entity::Task,
+            id,
+        })
+        | Some(KyubeyError::NoLogs { id })
+        | Some(KyubeyError::LogUnavailable { id, .. }) => {
+            let mut parts = id.split('/');
+            if let (Some(run_id), Some(task_id)) = (parts.next(), parts.next()) {
+                if parts.next().is_some() {
+                    suggestions.push((
+                        format!("logs/{run_id}/{task_id}"),
+                        format!("See the latest attempt of Task {task_id}"),
+                    ));
+                }
+                suggestions.push((
+                    format!("tasks/{run_id}"),
+                    format!("See the other Tasks of Dag Run {run_id}"),
+                ));
+            }
+        }
+        Some(KyubeyError::NotFound {
+            entity: Entity::FailedDagRun,
+            ..
+        }) => suggestions.push(("failures".to_string(), "See failed Dag Runs".to_string())),
+        Some(KyubeyError::NoParentSystem { .. }) => suggestions.push((
+            "attention".to_string(),
+            "See what needs attention".to_string(),
+        )),
+        _ if err.status().is_server_error() => {
+            suggestions.push((path.to_string(), "Try again".to_string()))
+        }
+        _ => {}
+    }
+
+    // Search always gets users back on track
+    suggestions.push(("/".to_string(), "Search for a System".to_string()));
+
+    suggestions
+}
 
 /// Index Page
 #[handler]
-pub async fn index(Data(pool): Data<&PgPool>) -> Result<Markup, poem::Error> {
-    // Start Transaction
-    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(InternalServerError)?;
-
+pub async fn index(Data(environments): Data<&Vec<Environment>>) -> Result<Markup, poem::Error> {
     // Pull the top of the list to pre-render the page.
-    let search: Markup = search_systems_component(&mut tx, "", &0).await?;
+    let search: Markup = search_systems_component(environments, "", &0).await?;
+
+    // Searches cover every environment, so they are saved in the first one that can keep them
+    let saved_searches: Option<Markup> = match environments
+        .iter()
+        .find(|environment: &&Environment| environment.config.kyubey_schema)
+    {
+        Some(environment) => {
+            let mut tx: Transaction<'_, Postgres> =
+                environment.pool.begin().await.map_err(database_error)?;
+            Some(saved_searches_component(&mut tx, &environment.config).await?)
+        }
+        None => None,
+    };
 
     Ok(base_layout(
         "Search",
         &None,
         &None,
         &None,
+        &None,
         html! {
             // Search for a System
             fieldset class="fieldset m-8 animate-fade" {
@@ -48,12 +205,24 @@ pub async fn index(Data(pool): Data<&PgPool>) -> Result<Markup, poem::Error> {
                     hx-trigger="input changed delay:500ms, keyup[key=='Enter']"
                     hx-target="#search_results"
                     hx-swap="innerHTML";
-
+                @if let Some(saved_searches) = saved_searches {
+                    (saved_searches)
+                }
+                @for environment in environments {
+                    @let name: &str = &environment.config.environment;
+                    div {
+                        span class="badge badge-outline badge-sm mr-2" { (name) }
+                        a class="link" href={ "/" (name) "/scorecard" } { "Reliability Scorecard" }
+                        a class="link ml-4" href={ "/" (name) "/attention" } { "Attention" }
+                        a class="link ml-4" href={ "/" (name) "/failures" } { "Unacknowledged Failures" }
+                    }
+                }
             }
             // Search Results
             table class="table table-zebra table-sm animate-fade" {
                 thead {
                     tr {
+                        th { "Environment" }
                         th { "Client Name" }
                         th { "Client ID" }
                         th { "System Name" }
@@ -70,60 +239,181 @@ pub async fn index(Data(pool): Data<&PgPool>) -> Result<Markup, poem::Error> {
     ))
 }
 
+/// Webpage to list stuck Dag Runs and long running Dag Runs and Tasks
+#[handler]
+pub async fn attention(
+    Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
+) -> Result<Markup, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Everything that needs attention right now
+    let anomalies: Vec<Anomaly> = attention_read(&mut tx).await.map_err(http_error)?;
+
+    Ok(base_layout(
+        "Attention",
+        &Some(config.environment.clone()),
+        &None,
+        &None,
+        &None,
+        html! {
+            @if anomalies.is_empty() {
+                p class="text-center opacity-60 animate-fade" { "Nothing needs attention right now" }
+            } @else {
+                table class="table table-zebra table-sm animate-fade" {
+                    thead {
+                        tr {
+                            th { "Problem" }
+                            th { "System" }
+                            th { "Dag ID" }
+                            th { "Run ID" }
+                            th { "Task ID" }
+                            th { "Since" }
+                            th { "Elapsed" }
+                            th { "Usual" }
+                        }
+                    }
+                    tbody class="animate-fade-up" {
+                        @for anomaly in &anomalies {
+                            tr {
+                                td { span class={ "badge " (anomaly_badge_type(&anomaly.kind)) } { (anomaly.kind) } }
+                                td {
+                                    a class="link" href={ "dag_runs/" (anomaly.system_id) } {
+                                        (anomaly.client_name) " / " (anomaly.system_name)
+                                    }
+                                }
+                                td { (anomaly.dag_id) }
+                                td { a class="link" href={ "tasks/" (anomaly.run_id) } { (anomaly.run_id) } }
+                                td {
+                                    @if let Some(task_id) = &anomaly.task_id {
+                                        a class="link" href={ "logs/" (anomaly.run_id) "/" (task_id) } { (task_id) }
+                                    }
+                                }
+                                td { (anomaly.since) }
+                                td class="text-error" { (format_duration(&anomaly.elapsed)) }
+                                td { @if let Some(usual) = anomaly.usual { (format_duration(&usual)) } }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+    ))
+}
+
+/// Webpage for the queue of failed Dag Runs, and who is looking after them
+#[handler]
+pub async fn failures(
+    Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
+    Query(params): Query<FailuresParams>,
+) -> Result<Markup, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Pre-render the failure queue
+    let failures: Markup =
+        failures_component(&mut tx, config, &params.status, &params.assignee()).await?;
+
+    Ok(base_layout(
+        "Failures",
+        &Some(config.environment.clone()),
+        &None,
+        &None,
+        &None,
+        html! {
+            // Who is acting on failures, kept outside the queue so it survives a refresh
+            div class="flex justify-end ml-8 mr-8 mb-4" {
+                input id="failure_by" class="input input-sm w-48" type="text" name="by" placeholder="Your name";
+            }
+            div class="animate-fade" { (failures) }
+        },
+    ))
+}
+
+/// Webpage to compare how reliable each System and Dag has been
+#[handler]
+pub async fn scorecard(
+    Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
+    Query(params): Query<ScorecardParams>,
+) -> Result<Markup, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Pre-render the scorecard
+    let scorecard: Markup =
+        scorecard_component(&mut tx, &params.window, &params.sort, &params.descending).await?;
+
+    Ok(base_layout(
+        "Scorecard",
+        &Some(config.environment.clone()),
+        &None,
+        &None,
+        &None,
+        html! {
+            div class="animate-fade" { (scorecard) }
+        },
+    ))
+}
+
+/// Day picked on the Dag Run calendar
+#[derive(Deserialize)]
+struct DagRunsParams {
+    day: Option<NaiveDate>,
+}
+
 /// Webpage to view dag runs for a system
 #[handler]
 pub async fn dag_runs(
     Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
     Path(system_id): Path<String>,
+    Query(params): Query<DagRunsParams>,
 ) -> Result<Markup, poem::Error> {
     // Start Transaction
-    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(InternalServerError)?;
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
 
-    // Search for anything that meets our criteria
-    let dag_runs: SystemDagRuns = dag_runs_for_system_read(&mut tx, &system_id).await?;
+    // The System, and how it has been doing, these do not refresh
+    let system: System = system_read(&mut tx, &system_id).await.map_err(http_error)?;
+    let trend: Vec<DurationPoint> = system_duration_trend_read(&mut tx, &system_id)
+        .await
+        .map_err(http_error)?;
+    let calendar: Vec<CalendarDay> = system_calendar_read(&mut tx, &system_id)
+        .await
+        .map_err(http_error)?;
+
+    // Dag Runs, refreshing themselves while any are active
+    let dag_runs: Markup = dag_runs_component(&mut tx, config, &system_id, &params.day, &None)
+        .await?
+        .unwrap_or_default();
+
+    // Notes left on the System
+    let notes: Markup = notes_component(&mut tx, config, &NoteTarget::system(&system_id)).await?;
 
     Ok(base_layout(
         "Dag Runs",
+        &Some(config.environment.clone()),
         &Some(system_id),
         &None,
         &None,
         html! {
-            div class="animate-fade" { (system_stats(&dag_runs.system)) }
-            // Dag Run Table
-            table class="table table-zebra table-sm animate-fade" {
-                thead {
-                    tr {
-                        th { "Dag ID" }
-                        th { "Execution Date" }
-                        th { "State" }
-                        th { "Run ID" }
-                        th { "Start Date" }
-                        th { "End Date" }
-                    }
-                }
-                tbody class="animate-fade-up" {
-                    @for dag_run in dag_runs.dag_runs {
-                        tr
-                            id={ "row_" (dag_run.run_id) }
-                            class="hover:bg-base-300 cursor-pointer"
-                            onclick={ "window.location='/tasks/" (dag_run.run_id) "';"} {
-                            // Dag ID and Execution Date
-                            td { (dag_run.dag_id) }
-                            td { (dag_run.execution_date) }
-                            // Dag State Badge
-                            @if let Some(state) = dag_run.state {
-                                td class={ "badge " (dag_state_badge_type(&state)) } { (state) }
-                            } @else {
-                                td {}
-                            }
-                            td { (dag_run.run_id) }
-                            // Start and End Dates
-                            td { @if let Some(start_date) = dag_run.start_date { (start_date) } }
-                            td { @if let Some(end_date) = dag_run.end_date { (end_date) } }
+            div class="animate-fade" {
+                (system_stats(&system, &trend))
+                // Dag Run Calendar
+                div class="ml-8 mr-8 mt-4 mb-4" {
+                    (calendar_heatmap(&system.system_id, &calendar, &params.day))
+                    @if let Some(day) = params.day {
+                        div class="text-sm" {
+                            "Showing Dag Runs from " (day) " "
+                            a class="link" href={ "dag_runs/" (system.system_id) } { "Show all" }
                         }
                     }
                 }
+                (dag_runs)
             }
+            (notes)
         },
     ))
 }
@@ -132,90 +422,266 @@ pub async fn dag_runs(
 #[handler]
 pub async fn tasks(
     Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
     Path(run_id): Path<String>,
 ) -> Result<Markup, poem::Error> {
     // Start Transaction
-    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(InternalServerError)?;
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
 
-    // Search for anything that meets our criteria
-    let tasks: DagRunTasks = tasks_for_dag_run_read(&mut tx, &run_id).await?;
+    // Pull the DAG Run
+    let dag_run: DagRun = dag_run_read(&mut tx, config, &run_id)
+        .await
+        .map_err(http_error)?;
 
     // Make sure our dag run has a parent system
-    let system_id: String = match &tasks.dag_run.system_id {
+    let system_id: String = match &dag_run.system_id {
         Some(system_id) => Ok(system_id.to_string()),
-        None => Err(poem::Error::from_string(
-            "No Parent System Found",
-            StatusCode::NOT_FOUND,
-        )),
-    }?;
+        None => Err(KyubeyError::NoParentSystem {
+            run_id: run_id.clone(),
+        }),
+    }
+    .map_err(http_error)?;
 
     // Pull System details
-    let system: System = system_read(&mut tx, &system_id).await?;
+    let system: System = system_read(&mut tx, &system_id).await.map_err(http_error)?;
+
+    // Tasks, refreshing themselves while the Dag Run is active
+    let tasks: Markup = tasks_component(&mut tx, config, &run_id, &None)
+        .await?
+        .unwrap_or_default();
+
+    // Notes left on the Dag Run
+    let notes: Markup = notes_component(&mut tx, config, &NoteTarget::dag_run(&run_id)).await?;
 
     Ok(base_layout(
         "Tasks",
+        &Some(config.environment.clone()),
         &Some(system_id),
         &Some(run_id),
         &None,
         html! {
-            (system_stats(&system))
-            div class="animate-fade" { (dag_run_stats(&tasks.dag_run)) }
-            // Task Table
+            (system_stats(&system, &[]))
+            div class="animate-fade" {
+                (dag_run_stats(&dag_run, None))
+                (tasks)
+            }
+            (notes)
+        },
+    ))
+}
+
+/// Webpage to view how long a System's Dags and Tasks take
+#[handler]
+pub async fn analytics(
+    Data(pool): Data<&PgPool>,
+    Data(config): Data<&Config>,
+    Path(system_id): Path<String>,
+) -> Result<Markup, poem::Error> {
+    // Start Transaction
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
+
+    // Crunch the numbers
+    let durations: SystemDurations = system_durations_read(&mut tx, &system_id)
+        .await
+        .map_err(http_error)?;
+    let flaky: SystemFlakyTasks = system_flaky_tasks_read(&mut tx, config, &system_id)
+        .await
+        .map_err(http_error)?;
+    let slas: SystemSlas = system_slas_read(&mut tx, config, &system_id)
+        .await
+        .map_err(http_error)?;
+
+    Ok(base_layout(
+        "Analytics",
+        &Some(config.environment.clone()),
+        &Some(system_id),
+        &None,
+        &None,
+        html! {
+            div class="animate-fade" { (system_stats(&durations.system, &durations.trend)) }
+            // SLA Breaches
+            @if !slas.slas.is_empty() {
+                div class="divider" { "SLA Breaches" }
+                @if slas.breaches.is_empty() {
+                    p class="text-center opacity-60 animate-fade" { "Every Dag Run has kept its SLA" }
+                } @else {
+                    table class="table table-zebra table-sm animate-fade" {
+                        thead {
+                            tr {
+                                th { "Dag ID" }
+                                th { "Execution Date" }
+                                th { "State" }
+                                th { "Run ID" }
+                                th { "Start Date" }
+                                th { "End Date" }
+                            }
+                        }
+                        tbody class="animate-fade-up" {
+                            @for dag_run in &slas.breaches {
+                                tr
+                                    class="hover:bg-base-300 cursor-pointer"
+                                    onclick={ "window.location='tasks/" (dag_run.run_id) "';"} {
+                                    td { (dag_run.dag_id) }
+                                    td { (dag_run.execution_date) }
+                                    td {
+                                        @if let Some(state) = &dag_run.state {
+                                            span class={ "badge " (dag_state_badge_type(state)) } { (state) }
+                                        }
+                                    }
+                                    td { (dag_run.run_id) }
+                                    td { @if let Some(start_date) = dag_run.start_date { (start_date) } }
+                                    td { @if let Some(end_date) = dag_run.end_date { (end_date) } }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            // Flaky Tasks
+            div class="divider" { "Flaky Tasks" }
+            @if flaky.tasks.is_empty() {
+                p class="text-center opacity-60 animate-fade" { "No tasks needed retries lately" }
+            } @else {
+                table class="table table-zebra table-sm animate-fade" {
+                    thead {
+                        tr {
+                            th { "Dag ID" }
+                            th { "Task ID" }
+                            th { "Runs" }
+                            th { "Retry Rate" }
+                            th { "Mean Tries" }
+                            th { "Succeeded on Retry" }
+                            th { "Retries Exhausted" }
+                            th { "Failed Attempts" }
+                        }
+                    }
+                    tbody class="animate-fade-up" {
+                        @for task in &flaky.tasks {
+                            tr {
+                                td { (task.dag_id) }
+                                td { (task.task_id) }
+                                td class="text-center" { (task.runs) }
+                                td class=(success_rate_text_type(&(1.0 - task.retry_rate))) { (format_rate(&task.retry_rate)) }
+                                td { (format!("{:.2}", task.mean_tries)) }
+                                td class="text-center" { (task.flaky_successes) }
+                                td class="text-center" {
+                                    @if task.exhausted > 0 {
+                                        span class="badge badge-error" { (task.exhausted) }
+                                    }
+                                }
+                                td class="text-center" { (task.failed_attempts) }
+                            }
+                        }
+                    }
+                }
+            }
+            // Dag Durations
+            div class="divider" { "Dag Durations" }
+            table class="table table-zebra table-sm animate-fade" {
+                thead {
+                    tr {
+                        th { "Dag ID" }
+                        th { "Runs" }
+                        th { "Median" }
+                        th { "P90" }
+                        th { "P99" }
+                        th { "Trend" }
+                        th { "Outliers" }
+                    }
+                }
+                tbody class="animate-fade-up" {
+                    @for dag in &durations.dags {
+                        @let medians: Vec<f64> = dag.trend.iter().map(|point| point.median).collect();
+                        tr {
+                            td { (dag.dag_id) }
+                            td class="text-center" { (dag.stats.runs) }
+                            td { (format_duration(&dag.stats.median)) }
+                            td { (format_duration(&dag.stats.p90)) }
+                            td { (format_duration(&dag.stats.p99)) }
+                            td class="text-primary" { (sparkline(&medians)) }
+                            td class="text-center" {
+                                @if !dag.outliers.is_empty() {
+                                    span class="badge badge-warning" { (dag.outliers.len()) }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            // Task Durations
+            div class="divider" { "Task Durations" }
             table class="table table-zebra table-sm animate-fade" {
                 thead {
                     tr {
+                        th { "Dag ID" }
                         th { "Task ID" }
-                        th { "State" }
-                        th { "Start Date" }
-                        th { "End Date" }
-                        th { "Attempts" }
+                        th { "Runs" }
+                        th { "Median" }
+                        th { "P90" }
+                        th { "P99" }
+                        th { "Trend" }
+                        th { "Outliers" }
                     }
                 }
                 tbody class="animate-fade-up" {
-                    @for task in tasks.tasks {
-                        // Pre-compute / formate some values
-                        @let try_number: u32 = task.try_number.unwrap_or(0);
-                        @let state_cell: Markup = match task.state {
-                            Some(state) => html! {
-                                td class={ "badge " (task_state_badge_type(&state)) } { (state) }
-                            },
-                            None => html! { td {} },
-                        };
-                        @let start_date: String = match task.start_date {
-                            Some(start_date) => start_date.to_string(),
-                            None => "".to_string()
-                        };
-                        @let end_date: String = match task.end_date {
-                            Some(end_date) => end_date.to_string(),
-                            None => "".to_string()
-                        };
-                        // Should the row link to the logs?
-                        @if try_number > 0 {
+                    @for task in &durations.tasks {
+                        @let medians: Vec<f64> = task.trend.iter().map(|point| point.median).collect();
+                        tr {
+                            td { (task.dag_id) }
+                            td { (task.task_id) }
+                            td class="text-center" { (task.stats.runs) }
+                            td { (format_duration(&task.stats.median)) }
+                            td { (format_duration(&task.stats.p90)) }
+                            td { (format_duration(&task.stats.p99)) }
+                            td class="text-primary" { (sparkline(&medians)) }
+                            td class="text-center" {
+                                @if !task.outliers.is_empty() {
+                                    span class="badge badge-warning" { (task.outliers.len()) }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            // Outlier Runs
+            div class="divider" { "Outlier Runs" }
+            table class="table table-zebra table-sm animate-fade" {
+                thead {
+                    tr {
+                        th { "Dag ID" }
+                        th { "Task ID" }
+                        th { "Run ID" }
+                        th { "Execution Date" }
+                        th { "Duration" }
+                        th { "Median" }
+                    }
+                }
+                tbody class="animate-fade-up" {
+                    @for dag in &durations.dags {
+                        @for outlier in &dag.outliers {
                             tr
-                                id={ "row_" (task.task_id) }
                                 class="hover:bg-base-300 cursor-pointer"
-                                onclick={ "window.location='/logs/" (task.run_id) "/" (task.task_id) "';" } {
-                                td { (task.task_id) }
-                                // Task State Badge
-                                (state_cell)
-                                // Start and End Date
-                                td { (start_date) }
-                                td { (end_date) }
-                                // Attepts
-                                td class="text-center" { (try_number) }
+                                onclick={ "window.location='tasks/" (outlier.run_id) "';" } {
+                                td { (dag.dag_id) }
+                                td {}
+                                td { (outlier.run_id) }
+                                td { (outlier.execution_date) }
+                                td class="text-warning" { (format_duration(&outlier.duration)) }
+                                td { (format_duration(&dag.stats.median)) }
                             }
-                        } @else {
+                        }
+                    }
+                    @for task in &durations.tasks {
+                        @for outlier in &task.outliers {
                             tr
-                                id={ "row_" (task.task_id) }
-                                class="hover:bg-base-300" {
+                                class="hover:bg-base-300 cursor-pointer"
+                                onclick={ "window.location='logs/" (outlier.run_id) "/" (task.task_id) "';" } {
+                                td { (task.dag_id) }
                                 td { (task.task_id) }
-                                // Task State Badge
-                                (state_cell)
-                                // Start and End Date
-                                td { (start_date) }
-                                td { (end_date) }
-                                // Attepts
-                                td  {}
+                                td { (outlier.run_id) }
+                                td { (outlier.execution_date) }
+                                td class="text-warning" { (format_duration(&outlier.duration)) }
+                                td { (format_duration(&task.stats.median)) }
                             }
                         }
                     }
@@ -225,52 +691,82 @@ pub async fn tasks(
     ))
 }
 
+/// Which attempt and log level to open the logs page on
+#[derive(Deserialize)]
+struct LogsParams {
+    attempt: Option<u32>,
+    min_level: Option<LogLevel>,
+}
+
 /// Webpage to view logs for a task run
 #[handler]
 pub async fn logs(
     Data(config): Data<&Config>,
     Data(pool): Data<&PgPool>,
     Path((run_id, task_id)): Path<(String, String)>,
+    Query(params): Query<LogsParams>,
 ) -> Result<Markup, poem::Error> {
     // Start Transaction
-    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(InternalServerError)?;
+    let mut tx: Transaction<'_, Postgres> = pool.begin().await.map_err(database_error)?;
 
     // Pull system, dag run and task details
-    let system: System = system_for_dag_run_read(&mut tx, &run_id).await?;
-    let dag_run: DagRun = dag_run_read(&mut tx, &run_id).await?;
-    let task: Task = task_read(&mut tx, &run_id, &task_id).await?;
+    let system: System = system_for_dag_run_read(&mut tx, &run_id)
+        .await
+        .map_err(http_error)?;
+    let dag_run: DagRun = dag_run_read(&mut tx, config, &run_id)
+        .await
+        .map_err(http_error)?;
+    let task: Task = task_read(&mut tx, &run_id, &task_id)
+        .await
+        .map_err(http_error)?;
 
     // Make sure we should have logs from a run
     let try_number: u32 = match task.try_number {
         Some(try_number) if try_number > 0 => Ok(try_number),
-        _ => Err(poem::Error::from_string(
-            "No logs yet",
-            StatusCode::NOT_FOUND,
+        _ => Err(KyubeyError::NoLogs {
+            id: format!("{run_id}/{task_id}"),
+        }),
+    }
+    .map_err(http_error)?;
+
+    // Use the latest run as the attempt on page load, unless the URL asks for one
+    let attempt: u32 = match params.attempt {
+        None => Ok(try_number),
+        Some(attempt) if (1..=try_number).contains(&attempt) => Ok(attempt),
+        Some(attempt) => Err(KyubeyError::not_found(
+            Entity::Task,
+            &format!("{run_id}/{task_id}/attempt={attempt}"),
         )),
-    }?;
+    }
+    .map_err(http_error)?;
 
     // Pull the log component
     let log: Markup = log_component(
+        &mut tx,
         config,
         &dag_run.dag_id,
         &dag_run.run_id,
         &task.task_id,
-        &try_number, // Use the latest run as the attempt on page load
+        &attempt,
         &try_number,
+        &params.min_level,
     )
     .await?;
 
     Ok(base_layout(
         "Tasks",
+        &Some(config.environment.clone()),
         &Some(system.system_id.clone()),
         &Some(run_id),
         &Some(task_id),
         html! {
-            (system_stats(&system))
-            (dag_run_stats(&dag_run))
+            (system_stats(&system, &[]))
+            (dag_run_stats(&dag_run, None))
             div class="animate-fade" { (task_stats(&task)) }
             // Logs Text Box
             (log)
+            // Link to and highlight log lines
+            (log_line_script())
         },
     ))
 }
@@ -1,36 +1,55 @@
-use crate::{
-    core::{DagRun, System, Task},
-    ui::util::{dag_state_badge_type, task_state_badge_type},
+use crate::ui::util::{
+    dag_state_badge_type, dag_state_fill_type, format_duration, markdown, sla_status_badge_type,
+    task_state_badge_type,
 };
-use maud::{Markup, html};
+use chrono::{Datelike, NaiveDate, TimeDelta, Utc};
+use kyubey::core::{
+    CalendarDay, DagRun, DurationPoint, Note, NoteSource, NoteTarget, System, Task, calendar_start,
+};
+use maud::{Markup, PreEscaped, html};
+use std::collections::HashMap;
 
 /// HTML Page Head
-pub fn head() -> Markup {
+pub fn head(environment: &Option<String>) -> Markup {
     html! {
         head {
             title {"／人◕ ‿‿ ◕人＼"}
+            // Links without a leading slash, including htmx requests, stay within the environment
+            @if let Some(environment) = environment {
+                base href={ "/" (environment) "/" };
+            }
             meta name="description" content="Inspect Airflow Systems and Dag Runs";
             meta name="keywords" content="Airflow, Dag Runs, Kyubey, Logs, Search, Tasks";
             meta name="viewport" content="width=device-width, initial-scale=1.0";
             link rel="icon" type="image/x-icon" href="/assets/images/favicon.ico";
             link rel="stylesheet" type="text/css" href="/assets/css/main.css";
+            // Parse responses in a template, so table rows can be swapped on their own
+            meta name="htmx-config" content=r#"{"useTemplateFragments":true}"#;
             script defer src="/assets/scripts/htmx.js" {}
         }
     }
 }
 
 /// NavBar
-fn navbar(system_id: &Option<String>, run_id: &Option<String>, task_id: &Option<String>) -> Markup {
+fn navbar(
+    environment: &Option<String>,
+    system_id: &Option<String>,
+    run_id: &Option<String>,
+    task_id: &Option<String>,
+) -> Markup {
     html! {
         nav class="breadcrumbs ml-8" {
             ul {
               li { a href="/" { "Search" } }
+                @if let Some(environment) = environment {
+                    li { span class="badge badge-outline badge-sm" { (environment) } }
+                }
                 @if let Some(system_id) = system_id {
-                    li { a href={ "/dag_runs/" (system_id) } { "DagRuns" } }
+                    li { a href={ "dag_runs/" (system_id) } { "DagRuns" } }
                     @if let Some(run_id) = run_id {
-                        li { a href={ "/tasks/" (run_id) } { "Tasks" } }
+                        li { a href={ "tasks/" (run_id) } { "Tasks" } }
                         @if let Some(task_id) = task_id {
-                            li { a href={ "/logs/" (run_id) "/" (task_id) } { "Logs" } }
+                            li { a href={ "logs/" (run_id) "/" (task_id) } { "Logs" } }
                         }
                     }
                 }
@@ -54,20 +73,21 @@ fn page_title(title: &str) -> Markup {
 /// Header at the top of every page
 pub fn header(
     title: &str,
+    environment: &Option<String>,
     system_id: &Option<String>,
     run_id: &Option<String>,
     task_id: &Option<String>,
 ) -> Markup {
     html! {
         header {
-            (navbar(system_id, run_id, task_id))
+            (navbar(environment, system_id, run_id, task_id))
             (page_title(title))
         }
     }
 }
 
-/// List out Sysetem Details
-pub fn system_stats(system: &System) -> Markup {
+/// List out Sysetem Details, with how long Dag Runs have been taking if we know
+pub fn system_stats(system: &System, trend: &[DurationPoint]) -> Markup {
     html! {
         div class="stats shadow" {
             // Client
@@ -83,19 +103,38 @@ pub fn system_stats(system: &System) -> Markup {
                 div class="stat-desc" { "ID: " (system.system_id) }
             }
             // Dag Runs
-            div class="stat" {
-                div class="stat-title" { "Dag Runs" }
-                div class="stat-value text-left" { (system.number_of_dag_runs) }
-                div class="stat-desc" { "Latest: " (system.latest_run) }
+            (system_dag_runs_stat(system, None))
+            // Dag Run Durations
+            @if let Some(latest) = trend.last() {
+                @let medians: Vec<f64> = trend.iter().map(|point: &DurationPoint| point.median).collect();
+                div class="stat" {
+                    div class="stat-title" { "Dag Run Duration" }
+                    div class="stat-value text-primary" { (sparkline(&medians)) }
+                    div class="stat-desc" {
+                        "Median: " (format_duration(&latest.median)) " "
+                        a class="link" href={ "analytics/" (system.system_id) } { "Analytics" }
+                    }
+                }
             }
         }
     }
 }
 
-/// List out Dag Run Details
-pub fn dag_run_stats(dag_run: &DagRun) -> Markup {
+/// How many Dag Runs a System has had, kept up to date by refreshing Dag Run tables
+pub fn system_dag_runs_stat(system: &System, swap_oob: Option<String>) -> Markup {
     html! {
-        div class="stats shadow" {
+        div id="system_dag_runs" class="stat" hx-swap-oob=[swap_oob] {
+            div class="stat-title" { "Dag Runs" }
+            div class="stat-value text-left" { (system.number_of_dag_runs) }
+            div class="stat-desc" { "Latest: " (system.latest_run) }
+        }
+    }
+}
+
+/// List out Dag Run Details, kept up to date by refreshing Task tables
+pub fn dag_run_stats(dag_run: &DagRun, swap_oob: Option<String>) -> Markup {
+    html! {
+        div id="dag_run_stats" class="stats shadow" hx-swap-oob=[swap_oob] {
             // Dag In and Run ID
             div class="stat" {
                 div class="stat-title" { "DAG ID" }
@@ -119,7 +158,11 @@ pub fn dag_run_stats(dag_run: &DagRun) -> Markup {
                 } @else {
                     div class="stat-value" {}
                 }
-                div class="stat-desc" {}
+                div class="stat-desc" {
+                    @if let Some(sla_status) = &dag_run.sla_status {
+                        span class={ "badge badge-sm " (sla_status_badge_type(sla_status)) } { (sla_status) }
+                    }
+                }
             }
         }
     }
@@ -161,3 +204,282 @@ pub fn task_stats(task: &Task) -> Markup {
         }
     }
 }
+
+/// Tabs to switch between the attempts of a task
+pub fn log_attempt_tabs(
+    dag_id: &str,
+    run_id: &str,
+    task_id: &str,
+    attempt: &Option<u32>,
+    try_number: &u32,
+    level_param: &str,
+) -> Markup {
+    html! {
+        div role="tablist" class="tabs tabs-box" {
+            @for current_try in 1..=*try_number {
+                // Highlight the current attempt tab
+                @if Some(current_try) == *attempt {
+                    a
+                        role="tab"
+                        class="tab tab-active" {
+                        "Attempt: " (current_try)
+                    }
+                // Have all other attemps link to their logs
+                } @else {
+                    a
+                        role="tab"
+                        class="tab"
+                        hx-get={ "component/log?dag_id=" (dag_id) "&run_id=" (run_id) "&task_id=" (task_id) "&attempt=" (current_try) (level_param) }
+                        hx-push-url={ "logs/" (run_id) "/" (task_id) "?attempt=" (current_try) (level_param) }
+                        hx-trigger="click"
+                        hx-swap="outerHTML"
+                        hx-target="#logs" {
+                        "Attempt: " (current_try)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Pick two attempts of a task to compare
+pub fn log_compare_form(
+    dag_id: &str,
+    run_id: &str,
+    task_id: &str,
+    left_attempt: &u32,
+    right_attempt: &u32,
+    try_number: &u32,
+) -> Markup {
+    html! {
+        form
+            class="join"
+            hx-get="component/log_diff"
+            hx-swap="outerHTML"
+            hx-target="#logs" {
+            input type="hidden" name="dag_id" value=(dag_id);
+            input type="hidden" name="run_id" value=(run_id);
+            input type="hidden" name="task_id" value=(task_id);
+            select class="select select-sm join-item" name="left_attempt" {
+                @for current_try in 1..=*try_number {
+                    option value=(current_try) selected[current_try == *left_attempt] { "Attempt: " (current_try) }
+                }
+            }
+            select class="select select-sm join-item" name="right_attempt" {
+                @for current_try in 1..=*try_number {
+                    option value=(current_try) selected[current_try == *right_attempt] { "Attempt: " (current_try) }
+                }
+            }
+            button class="btn btn-sm join-item" type="submit" { "Compare" }
+        }
+    }
+}
+
+/// Highlight and scroll to the lines in the URL (`#L12` or `#L12-L20`), clicking a line links to it
+pub fn log_line_script() -> Markup {
+    html! {
+        script {
+            (PreEscaped(r##"
+                function highlightLogLines(scroll) {
+                    document.querySelectorAll("#logs pre.bg-warning\\/30").forEach((line) => {
+                        line.classList.remove("bg-warning/30");
+                    });
+                    const range = window.location.hash.match(/^#L(\d+)(?:-L(\d+))?$/);
+                    if (!range) return;
+                    const lines = document.querySelectorAll("#logs pre[id^='L']");
+                    if (!lines.length) return;
+                    const start = Number(range[1]);
+                    const end = Number(range[2] || range[1]);
+                    const last = Number(lines[lines.length - 1].id.slice(1));
+                    let first = null;
+                    for (let number = Math.min(start, end); number <= Math.min(Math.max(start, end), last); number++) {
+                        const line = document.getElementById("L" + number);
+                        if (line) {
+                            line.classList.add("bg-warning/30");
+                            first = first || line;
+                        }
+                    }
+                    if (first && scroll) first.scrollIntoView({ block: "center" });
+                }
+                document.addEventListener("click", (event) => {
+                    const line = event.target.closest("#logs pre[id^='L']");
+                    if (!line || !window.getSelection().isCollapsed) return;
+                    const current = window.location.hash.match(/^#L(\d+)/);
+                    const number = Number(line.id.slice(1));
+                    let hash = "#L" + number;
+                    if (event.shiftKey && current) {
+                        const start = Number(current[1]);
+                        hash = "#L" + Math.min(start, number) + "-L" + Math.max(start, number);
+                    }
+                    history.replaceState(history.state, "", location.pathname + location.search + hash);
+                    highlightLogLines(false);
+                });
+                window.addEventListener("hashchange", () => highlightLogLines(true));
+                document.addEventListener("htmx:afterSettle", () => highlightLogLines(true));
+                document.addEventListener("DOMContentLoaded", () => highlightLogLines(true));
+            "##))
+        }
+    }
+}
+
+/// Tiny line chart of some values
+pub fn sparkline(values: &[f64]) -> Markup {
+    const WIDTH: f64 = 120.0;
+    const HEIGHT: f64 = 32.0;
+
+    // Scale to the largest value, and draw a flat line for a single value
+    let max: f64 = values.iter().copied().fold(f64::EPSILON, f64::max);
+    let values: Vec<f64> = match values {
+        [value] => vec![*value, *value],
+        _ => values.to_vec(),
+    };
+    let step: f64 = WIDTH / values.len().saturating_sub(1).max(1) as f64;
+    let points: String = values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let x: f64 = index as f64 * step;
+            let y: f64 = HEIGHT - value / max * (HEIGHT - 2.0) - 1.0;
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    html! {
+        svg class="inline-block" width=(WIDTH) height=(HEIGHT) viewBox={ "0 0 " (WIDTH) " " (HEIGHT) } {
+            polyline fill="none" stroke="currentColor" stroke-width="2" points=(points) {}
+        }
+    }
+}
+
+/// GitHub style calendar of Dag Runs per day, each day links to the runs from that day
+pub fn calendar_heatmap(
+    system_id: &str,
+    days: &[CalendarDay],
+    selected: &Option<NaiveDate>,
+) -> Markup {
+    const CELL: i64 = 11;
+    const STEP: i64 = 13;
+    const TOP: i64 = 15;
+
+    // Lay the calendar out in week columns, starting on a Sunday
+    let today: NaiveDate = Utc::now().date_naive();
+    let start: NaiveDate = calendar_start(&today);
+    let weeks: i64 = (today - start).num_days() / 7 + 1;
+    let by_date: HashMap<NaiveDate, &CalendarDay> = days
+        .iter()
+        .map(|day: &CalendarDay| (day.date, day))
+        .collect();
+    let busiest: u64 = days
+        .iter()
+        .map(|day: &CalendarDay| day.runs)
+        .max()
+        .unwrap_or(1);
+
+    html! {
+        svg
+            class="text-base-content"
+            width=(weeks * STEP)
+            height=(TOP + 7 * STEP)
+            viewBox={ "0 0 " (weeks * STEP) " " (TOP + 7 * STEP) } {
+            // Month labels over the first week of each month
+            @for week in 0..weeks {
+                @let sunday: NaiveDate = start + TimeDelta::weeks(week);
+                @if sunday.day() <= 7 {
+                    text x=(week * STEP) y="10" font-size="9" fill="currentColor" {
+                        (sunday.format("%b"))
+                    }
+                }
+            }
+            // One square per day, colored by the worst run state and shaded by run count
+            @for offset in 0..=(today - start).num_days() {
+                @let date: NaiveDate = start + TimeDelta::days(offset);
+                @let x: i64 = offset / 7 * STEP;
+                @let y: i64 = TOP + i64::from(date.weekday().num_days_from_sunday()) * STEP;
+                @let is_selected: bool = Some(date) == *selected;
+                @match by_date.get(&date) {
+                    Some(day) => {
+                        @let opacity: f64 = 0.4 + 0.6 * day.runs as f64 / busiest as f64;
+                        a href={ "dag_runs/" (system_id) "?day=" (date) } {
+                            rect
+                                class=(day.worst_state.as_ref().map_or("fill-base-300", dag_state_fill_type))
+                                x=(x)
+                                y=(y)
+                                width=(CELL)
+                                height=(CELL)
+                                rx="2"
+                                fill-opacity={ (format!("{:.2}", opacity)) }
+                                stroke=[is_selected.then_some("currentColor")] {
+                                title {
+                                    (date) ": " (day.runs) " runs"
+                                    @if let Some(state) = &day.worst_state { ", worst " (state) }
+                                }
+                            }
+                        }
+                    },
+                    None => {
+                        rect
+                            class="fill-base-300"
+                            x=(x)
+                            y=(y)
+                            width=(CELL)
+                            height=(CELL)
+                            rx="2"
+                            stroke=[is_selected.then_some("currentColor")] {
+                            title { (date) ": no runs" }
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Notes left on a System, Dag Run or Task attempt
+pub fn notes_list(notes: &[Note]) -> Markup {
+    html! {
+        @for note in notes {
+            div class="chat chat-start" {
+                div class="chat-header" {
+                    (note.author)
+                    time class="text-xs opacity-50 ml-2" { (note.created_at) }
+                    @if note.source == NoteSource::Airflow {
+                        span class="badge badge-xs badge-info ml-2" { "Airflow" }
+                    }
+                }
+                div class="chat-bubble [&_a]:link [&_code]:font-mono [&_em]:italic [&_ol]:list-decimal [&_ol]:ml-4 [&_p]:mb-1 [&_strong]:font-bold [&_ul]:list-disc [&_ul]:ml-4" {
+                    (markdown(&note.body))
+                }
+            }
+        }
+    }
+}
+
+/// Form to leave a note on a System, Dag Run or Task attempt
+pub fn note_form(target: &NoteTarget) -> Markup {
+    html! {
+        form
+            class="flex flex-col gap-2 mt-2"
+            hx-post="component/notes"
+            hx-target="#notes"
+            hx-swap="outerHTML" {
+            // What the note is left on
+            @if let Some(system_id) = &target.system_id {
+                input type="hidden" name="system_id" value=(system_id);
+            }
+            @if let Some(run_id) = &target.run_id {
+                input type="hidden" name="run_id" value=(run_id);
+            }
+            @if let Some(task_id) = &target.task_id {
+                input type="hidden" name="task_id" value=(task_id);
+            }
+            @if let Some(attempt) = &target.attempt {
+                input type="hidden" name="attempt" value=(attempt);
+            }
+            // The note itself
+            input class="input input-sm w-64" type="text" name="author" placeholder="Your name" required;
+            textarea class="textarea w-full" name="body" placeholder="Leave a note, markdown welcome" required {}
+            button class="btn btn-sm btn-primary w-32" type="submit" { "Add Note" }
+        }
+    }
+}
@@ -1,4 +1,10 @@
-use crate::core::{DagState, TaskState};
+use kyubey::core::{
+    AnomalyKind, AnsiColor, AnsiStyle, DagState, FailureStatus, LogChange, LogLevel, SlaStatus,
+    TaskState,
+};
+use maud::{Markup, PreEscaped};
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, html::push_html};
+use std::hash::{DefaultHasher, Hash, Hasher};
 
 /// Translate a DagState to a Badge Type
 pub fn dag_state_badge_type(state: &DagState) -> &'static str {
@@ -10,6 +16,43 @@ pub fn dag_state_badge_type(state: &DagState) -> &'static str {
     }
 }
 
+/// Translate an SlaStatus to a Badge Type
+pub fn sla_status_badge_type(status: &SlaStatus) -> &'static str {
+    match status {
+        SlaStatus::Breached => "badge-error",
+        SlaStatus::Met => "badge-success badge-outline",
+        SlaStatus::Pending => "badge-ghost",
+    }
+}
+
+/// Translate an AnomalyKind to a Badge Type
+pub fn anomaly_badge_type(kind: &AnomalyKind) -> &'static str {
+    match kind {
+        AnomalyKind::StuckQueued => "badge-warning",
+        AnomalyKind::LongRunningDagRun => "badge-error",
+        AnomalyKind::LongRunningTask => "badge-secondary",
+    }
+}
+
+/// Translate a FailureStatus to a Badge Type
+pub fn failure_status_badge_type(status: &FailureStatus) -> &'static str {
+    match status {
+        FailureStatus::Unacknowledged => "badge-error",
+        FailureStatus::Acknowledged => "badge-warning",
+        FailureStatus::Resolved => "badge-success",
+    }
+}
+
+/// Translate a DagState to an SVG Fill Color
+pub fn dag_state_fill_type(state: &DagState) -> &'static str {
+    match state {
+        DagState::Failed => "fill-error",
+        DagState::Queued => "fill-neutral",
+        DagState::Running => "fill-primary",
+        DagState::Success => "fill-success",
+    }
+}
+
 /// Translate a DagState to a Badge Type
 pub fn task_state_badge_type(state: &TaskState) -> &'static str {
     match state {
@@ -27,3 +70,240 @@ pub fn task_state_badge_type(state: &TaskState) -> &'static str {
         TaskState::UpstreamFailed => "badge-warning",
     }
 }
+
+/// Translate a LogLevel to a Text Color
+pub fn log_level_text_type(level: &LogLevel) -> &'static str {
+    match level {
+        LogLevel::Debug => "text-neutral-content/60",
+        LogLevel::Info => "",
+        LogLevel::Warning => "text-warning",
+        LogLevel::Error => "text-error",
+        LogLevel::Critical => "text-error font-bold",
+    }
+}
+
+/// Translate a LogChange to Background Colors for the left and right side of a diff
+pub fn log_change_background_type(change: &LogChange) -> (&'static str, &'static str) {
+    match change {
+        LogChange::Equal => ("", ""),
+        LogChange::Removed => ("bg-error/20", ""),
+        LogChange::Added => ("", "bg-success/20"),
+        LogChange::Changed => ("bg-error/20", "bg-success/20"),
+    }
+}
+
+/// Translate an AnsiColor to a Text Color
+fn ansi_text_type(color: &AnsiColor) -> &'static str {
+    match color {
+        AnsiColor::Black => "text-gray-500",
+        AnsiColor::Red => "text-red-400",
+        AnsiColor::Green => "text-green-400",
+        AnsiColor::Yellow => "text-yellow-400",
+        AnsiColor::Blue => "text-blue-400",
+        AnsiColor::Magenta => "text-fuchsia-400",
+        AnsiColor::Cyan => "text-cyan-400",
+        AnsiColor::White => "text-gray-200",
+        AnsiColor::BrightBlack => "text-gray-400",
+        AnsiColor::BrightRed => "text-red-300",
+        AnsiColor::BrightGreen => "text-green-300",
+        AnsiColor::BrightYellow => "text-yellow-300",
+        AnsiColor::BrightBlue => "text-blue-300",
+        AnsiColor::BrightMagenta => "text-fuchsia-300",
+        AnsiColor::BrightCyan => "text-cyan-300",
+        AnsiColor::BrightWhite => "text-white",
+    }
+}
+
+/// Translate an AnsiColor to a Background Color
+fn ansi_background_type(color: &AnsiColor) -> &'static str {
+    match color {
+        AnsiColor::Black => "bg-gray-900",
+        AnsiColor::Red => "bg-red-800",
+        AnsiColor::Green => "bg-green-800",
+        AnsiColor::Yellow => "bg-yellow-800",
+        AnsiColor::Blue => "bg-blue-800",
+        AnsiColor::Magenta => "bg-fuchsia-800",
+        AnsiColor::Cyan => "bg-cyan-800",
+        AnsiColor::White => "bg-gray-600",
+        AnsiColor::BrightBlack => "bg-gray-700",
+        AnsiColor::BrightRed => "bg-red-600",
+        AnsiColor::BrightGreen => "bg-green-600",
+        AnsiColor::BrightYellow => "bg-yellow-600",
+        AnsiColor::BrightBlue => "bg-blue-600",
+        AnsiColor::BrightMagenta => "bg-fuchsia-600",
+        AnsiColor::BrightCyan => "bg-cyan-600",
+        AnsiColor::BrightWhite => "bg-gray-400",
+    }
+}
+
+/// Translate an AnsiStyle to CSS Classes
+pub fn ansi_style_classes(style: &AnsiStyle) -> String {
+    let classes: [&str; 6] = [
+        style.foreground.as_ref().map_or("", ansi_text_type),
+        style.background.as_ref().map_or("", ansi_background_type),
+        if style.bold { "font-bold" } else { "" },
+        if style.dim { "opacity-60" } else { "" },
+        if style.italic { "italic" } else { "" },
+        if style.underline { "underline" } else { "" },
+    ];
+
+    classes
+        .into_iter()
+        .filter(|class: &&str| !class.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Fingerprint rendered HTML so we can tell if it changed
+pub fn fingerprint(markup: &Markup) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    markup.0.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Fingerprint a list of keys, so we can tell if the rows of a table moved
+pub fn fingerprint_keys(keys: &[&str]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    keys.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Format seconds as a short human readable duration
+pub fn format_duration(seconds: &f64) -> String {
+    let total: u64 = seconds.max(0.0).round() as u64;
+    let (hours, minutes, seconds) = (total / 3600, total % 3600 / 60, total % 60);
+
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, _) => format!("{}m {:02}s", minutes, seconds),
+        _ => format!("{}h {:02}m {:02}s", hours, minutes, seconds),
+    }
+}
+
+/// Format a 0 to 1 rate as a percentage
+pub fn format_rate(rate: &f64) -> String {
+    format!("{:.1}%", rate * 100.0)
+}
+
+/// Format a 0 to 1 rate as a percentage, or a dash when there was nothing to rate
+pub fn format_optional_rate(rate: &Option<f64>) -> String {
+    match rate {
+        Some(rate) => format_rate(rate),
+        None => "—".to_string(),
+    }
+}
+
+/// Translate a Success Rate to a Text Color
+pub fn success_rate_text_type(rate: &f64) -> &'static str {
+    match rate {
+        rate if *rate >= 0.95 => "text-success",
+        rate if *rate >= 0.8 => "text-warning",
+        _ => "text-error",
+    }
+}
+
+/// Only let links point at the web, email, or somewhere relative
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    let scheme: Option<&str> = url
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme: &&str| !scheme.contains(['/', '?', '#']));
+
+    match scheme.map(str::to_lowercase).as_deref() {
+        None | Some("http") | Some("https") | Some("mailto") => url,
+        Some(_) => CowStr::Borrowed("#"),
+    }
+}
+
+/// Render markdown as HTML, showing any raw HTML as text so notes can not inject markup
+pub fn markdown(text: &str) -> Markup {
+    let options: Options =
+        Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(text, options).map(|event: Event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        event => event,
+    });
+
+    let mut rendered: String = String::new();
+    push_html(&mut rendered, events);
+    PreEscaped(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_url_keeps_web_email_and_relative_links() {
+        for url in [
+            "https://example.com/a?b=c:d",
+            "HTTP://example.com",
+            "mailto:team@example.com",
+            "/default/tasks/run_1",
+            "tasks/run_1#L3",
+            "?page=2",
+        ] {
+            assert_eq!(safe_url(CowStr::Borrowed(url)).as_ref(), url);
+        }
+    }
+
+    #[test]
+    fn safe_url_drops_other_schemes() {
+        for url in [
+            "javascript:alert(1)",
+            "JavaScript:alert(1)",
+            "data:text/html;base64,PHNjcmlwdD4=",
+            "vbscript:msgbox(1)",
+            "java\tscript:alert(1)",
+        ] {
+            assert_eq!(safe_url(CowStr::Borrowed(url)).as_ref(), "#");
+        }
+    }
+
+    #[test]
+    fn markdown_neutralises_javascript_links() {
+        let rendered: String = markdown("[x](javascript:alert(1)) [y](javascript&#58;alert(1))").0;
+        assert!(!rendered.to_lowercase().contains("javascript"));
+        assert_eq!(rendered.matches(r##"href="#""##).count(), 2);
+
+        let rendered: String = markdown("![x](javascript:alert(1))").0;
+        assert!(rendered.contains(r##"src="#""##));
+    }
+
+    #[test]
+    fn markdown_shows_raw_html_as_text() {
+        let rendered: String = markdown("<script>alert(1)</script> <b onclick=\"x\">hi</b>").0;
+        assert!(!rendered.contains("<script"));
+        assert!(!rendered.contains("<b "));
+        assert!(rendered.contains("&lt;script&gt;"));
+    }
+
+    #[test]
+    fn markdown_renders_safe_links_and_formatting() {
+        let rendered: String = markdown("*hi* [ok](https://example.com)").0;
+        assert!(rendered.contains("<em>hi</em>"));
+        assert!(rendered.contains(r#"<a href="https://example.com">ok</a>"#));
+    }
+}